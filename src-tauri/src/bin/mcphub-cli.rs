@@ -0,0 +1,6 @@
+//! `mcphub-cli`, the command-line companion to the mcphub app. See the `cli` module.
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    std::process::exit(mcphub_lib::run_cli(&args));
+}
@@ -0,0 +1,330 @@
+use crate::config::{ConfigManager, ConfigType, MCPServer};
+use crate::error::{AppError, Result};
+use crate::import::{self, ImportOptions, ImportPreview, ImportReport};
+use crate::redact;
+use crate::signing::{self, BundlePolicy, BundleSignature, SignatureStatus, TrustStore, Verification};
+use crate::templates::{self, ParameterKind, ServerTemplate, TemplateInstance};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Schema version written by this build. Version 1 is the bare server map
+/// produced by `export_config`, which is still accepted on import.
+pub const BUNDLE_SCHEMA_VERSION: u32 = 2;
+
+/// File extension used for bundles
+pub const BUNDLE_EXTENSION: &str = ".mcphub.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleServer {
+    pub server: MCPServer,
+    /// Clients the server was enabled in when the bundle was made
+    pub clients: Vec<ConfigType>,
+    /// The template it was made from, one of the bundle's `templates`.
+    /// Secret values are left out, since they refer to the author's vault
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<TemplateInstance>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlePlaceholder {
+    pub name: String,
+    /// `server.location` pairs that use the placeholder, e.g. `github.env.GITHUB_TOKEN`
+    pub used_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bundle {
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Missing for bundles migrated from version 1
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub servers: BTreeMap<String, BundleServer>,
+    /// Templates the servers were made from, so they can be upgraded later
+    #[serde(default)]
+    pub templates: Vec<ServerTemplate>,
+    #[serde(default)]
+    pub placeholders: Vec<BundlePlaceholder>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<BundleSignature>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportBundleRequest {
+    /// Servers to include; all managed servers if omitted
+    pub servers: Option<Vec<String>>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub redact: bool,
+    /// Sign with the local signing key
+    #[serde(default)]
+    pub sign: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportBundleRequest {
+    pub json: String,
+    /// Limit the import to these clients; each server still only goes where the bundle enables it
+    pub targets: Option<Vec<ConfigType>>,
+    /// Accept a signer whose key isn't trusted yet, even when the policy blocks
+    #[serde(default)]
+    pub trust_signer: bool,
+    #[serde(flatten)]
+    pub options: ImportOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundlePreview {
+    pub bundle: Bundle,
+    pub verification: Verification,
+    pub import: ImportPreview,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleImportReport {
+    pub verification: Verification,
+    /// Templates that would have replaced a built-in or local one, which only
+    /// bundles signed by a trusted key may do
+    #[serde(default)]
+    pub left_out_templates: Vec<String>,
+    #[serde(flatten)]
+    pub report: ImportReport,
+}
+
+/// Build a bundle from the managed servers, keeping where each one is enabled.
+pub fn export(manager: &ConfigManager, request: &ExportBundleRequest) -> Result<Bundle> {
+    let managed = manager.get_managed_servers()?;
+
+    let state = manager.get_state();
+    let catalog = templates::catalog(&state.template_dirs)?.templates;
+    let mut servers = HashMap::new();
+    let mut clients = HashMap::new();
+    let mut instances = HashMap::new();
+    let mut used: Vec<ServerTemplate> = Vec::new();
+    for server in managed {
+        if request.servers.as_ref().is_some_and(|names| !names.contains(&server.name)) {
+            continue;
+        }
+        let enabled: Vec<ConfigType> = ConfigType::all()
+            .into_iter()
+            .filter(|ct| server.systems.get(ct.system_key()).is_some_and(|s| s.enabled))
+            .collect();
+        clients.insert(server.name.clone(), enabled);
+        servers.insert(server.name.clone(), server.to_server());
+
+        let Some(instance) = state.template_instances.get(&server.name) else {
+            continue;
+        };
+        let Some(template) = catalog.iter().find(|t| t.id == instance.template_id) else {
+            continue;
+        };
+        let mut instance = instance.clone();
+        instance.values.retain(|name, _| {
+            let parameter = template.parameters.iter().find(|p| &p.name == name);
+            parameter.is_some_and(|p| p.kind != ParameterKind::Secret)
+        });
+        instances.insert(server.name.clone(), instance);
+        if !used.iter().any(|t| t.id == template.id) {
+            let mut template = template.clone();
+            template.source = None;
+            template.overrides_builtin = false;
+            used.push(template);
+        }
+    }
+    used.sort_by(|a, b| a.id.cmp(&b.id));
+
+    if let Some(missing) = request
+        .servers
+        .iter()
+        .flatten()
+        .find(|name| !servers.contains_key(*name))
+    {
+        return Err(AppError::ServerNotFound(missing.clone()));
+    }
+
+    let mut placeholders: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    if request.redact {
+        let (redacted, redactions) = redact::redact_servers(&servers);
+        servers = redacted;
+        for r in redactions {
+            placeholders
+                .entry(r.placeholder)
+                .or_default()
+                .push(format!("{}.{}", r.server, r.location));
+        }
+    }
+
+    let mut bundle = Bundle {
+        schema_version: BUNDLE_SCHEMA_VERSION,
+        author: request.author.clone(),
+        description: request.description.clone(),
+        created_at: Some(Utc::now()),
+        servers: servers
+            .into_iter()
+            .map(|(name, server)| {
+                let clients = clients.remove(&name).unwrap_or_default();
+                let template = instances.remove(&name);
+                (name, BundleServer { server, clients, template })
+            })
+            .collect(),
+        templates: used,
+        placeholders: placeholders
+            .into_iter()
+            .map(|(name, used_by)| BundlePlaceholder { name, used_by })
+            .collect(),
+        signature: None,
+    };
+
+    if request.sign {
+        signing::sign(&mut bundle)?;
+    }
+    Ok(bundle)
+}
+
+/// Version 1 is a bare server map (or client file) with no client information,
+/// which the old import wrote to every client.
+fn migrate_v1(json: &str) -> Result<Bundle> {
+    let servers = import::parse_servers(json)?;
+    Ok(Bundle {
+        schema_version: BUNDLE_SCHEMA_VERSION,
+        author: None,
+        description: None,
+        created_at: None,
+        placeholders: redact::find_placeholders(&servers)
+            .into_iter()
+            .map(|name| BundlePlaceholder { name, used_by: Vec::new() })
+            .collect(),
+        servers: servers
+            .into_iter()
+            .map(|(name, server)| {
+                let clients = ConfigType::all();
+                (name, BundleServer { server, clients, template: None })
+            })
+            .collect(),
+        templates: Vec::new(),
+        signature: None,
+    })
+}
+
+/// Parse a bundle of any known version, migrating it to the current schema.
+pub fn parse(json: &str) -> Result<Bundle> {
+    let value: Value = serde_json::from_str(json)?;
+    let version = match value.get("schemaVersion") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| AppError::InvalidBundle("schemaVersion must be a number".to_string()))?,
+    };
+
+    match version {
+        1 => migrate_v1(json),
+        2 => serde_json::from_value(value).map_err(|e| AppError::InvalidBundle(e.to_string())),
+        v => Err(AppError::InvalidBundle(format!(
+            "Schema version {} is newer than this version of mcphub supports ({})",
+            v, BUNDLE_SCHEMA_VERSION
+        ))),
+    }
+}
+
+fn placement(bundle: &Bundle, targets: Option<&[ConfigType]>) -> HashMap<String, Vec<ConfigType>> {
+    bundle
+        .servers
+        .iter()
+        .map(|(name, entry)| {
+            let clients = entry
+                .clients
+                .iter()
+                .copied()
+                .filter(|ct| match targets {
+                    Some(targets) => targets.contains(ct),
+                    None => true,
+                })
+                .collect();
+            (name.clone(), clients)
+        })
+        .collect()
+}
+
+fn servers(bundle: &Bundle) -> HashMap<String, MCPServer> {
+    bundle
+        .servers
+        .iter()
+        .map(|(name, entry)| (name.clone(), entry.server.clone()))
+        .collect()
+}
+
+pub fn preview(manager: &ConfigManager, json: &str, targets: Option<&[ConfigType]>) -> Result<BundlePreview> {
+    let bundle = parse(json)?;
+    let verification = signing::verify(&bundle, &TrustStore::open()?)?;
+    let import = import::preview(manager, &servers(&bundle), &placement(&bundle, targets))?;
+    Ok(BundlePreview {
+        bundle,
+        verification,
+        import,
+    })
+}
+
+/// Import a bundle, enabling each server in the clients it was enabled in.
+/// Servers made from a template remember it, and templates the catalog
+/// doesn't have yet are kept in [`templates::bundled_dir`].
+///
+/// The signature is checked against the trust store first; see [`signing::enforce`].
+pub fn import(
+    manager: &mut ConfigManager,
+    request: &ImportBundleRequest,
+    policy: BundlePolicy,
+) -> Result<BundleImportReport> {
+    let bundle = parse(&request.json)?;
+    let verification = signing::verify(&bundle, &TrustStore::open()?)?;
+    signing::enforce(&bundle, &verification, policy, request.trust_signer)?;
+
+    let mut options = request.options.clone();
+    options.source_modified = options.source_modified.or(bundle.created_at);
+
+    let report = import::merge(
+        manager,
+        servers(&bundle),
+        &placement(&bundle, request.targets.as_deref()),
+        &options,
+    )?;
+
+    let dirs = manager.get_state().template_dirs.clone();
+    let trusted = verification.status == SignatureStatus::Trusted;
+    let mut kept = Vec::new();
+    let mut left_out_templates = Vec::new();
+    for template in &bundle.templates {
+        if templates::keep_bundled(template, &dirs, trusted)? {
+            kept.push(template.id.clone());
+        } else {
+            left_out_templates.push(template.id.clone());
+        }
+    }
+    for change in &report.changes {
+        let Some(ref written_as) = change.written_as else {
+            continue;
+        };
+        // Servers from a template left out aren't tied to the one with its id
+        let instance = bundle.servers.get(&change.name).and_then(|s| s.template.clone());
+        if let Some(instance) = instance.filter(|i| kept.contains(&i.template_id)) {
+            manager.record_template_instance(written_as, instance)?;
+        }
+    }
+    Ok(BundleImportReport {
+        verification,
+        left_out_templates,
+        report,
+    })
+}
@@ -0,0 +1,1140 @@
+//! Command-line companion to the mcphub app, for scripting config changes
+//! from dotfiles and CI. It reads and writes the same client configs and
+//! `~/.mcphub` state as the GUI, going through `mcphub daemon` when one is
+//! running so the two never hold separate copies of the state. It runs as
+//! `mcphub-cli`, or as `mcphub` given a command.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use crate::bundle::{BundleImportReport, ExportBundleRequest, ImportBundleRequest};
+use crate::config::{AppState, ConfigType, MCPServer, ManagedServer};
+use crate::diff::{self, ConfigDiff, DiffStatus};
+use crate::drift::{DriftReport, DriftStatus};
+use crate::error::{AppError, Result};
+use crate::health::TestResult;
+use crate::hub::{Backend, SaveServerRequest, SetEnabledRequest, SyncRequest};
+use crate::import::{ImportOptions, ImportReport, ImportRequest, ImportStrategy};
+use crate::marketplace::{InstallRequest, MarketplaceIndex, MarketplaceQuery, MarketplaceServer};
+use crate::metadata::{ServerMetadata, ServerQuery};
+use crate::plan::ChangePlan;
+use crate::sync::{SyncRule, SyncStrategy};
+use crate::templates::{
+    Catalog, InstantiateRequest, ParameterIssue, ServerTemplate, TemplateUpgrade, UpgradeRequest,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "mcphub-cli", version, about = "Manage MCP server configs across Claude Code, Claude Desktop and Roo Code")]
+struct Cli {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Client {
+    #[value(alias = "cc")]
+    ClaudeCode,
+    #[value(alias = "cd")]
+    ClaudeDesktop,
+    #[value(alias = "rc")]
+    RooCode,
+}
+
+impl From<Client> for ConfigType {
+    fn from(client: Client) -> Self {
+        match client {
+            Client::ClaudeCode => ConfigType::ClaudeCode,
+            Client::ClaudeDesktop => ConfigType::ClaudeDesktop,
+            Client::RooCode => ConfigType::RooCode,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Strategy {
+    Skip,
+    Overwrite,
+    Rename,
+    KeepNewest,
+}
+
+impl From<Strategy> for ImportStrategy {
+    fn from(strategy: Strategy) -> Self {
+        match strategy {
+            Strategy::Skip => ImportStrategy::Skip,
+            Strategy::Overwrite => ImportStrategy::Overwrite,
+            Strategy::Rename => ImportStrategy::Rename,
+            Strategy::KeepNewest => ImportStrategy::KeepNewest,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Merge {
+    SourceOfTruth,
+    Union,
+    Intersection,
+    PreferNewest,
+    ThreeWay,
+}
+
+impl From<Merge> for SyncStrategy {
+    fn from(merge: Merge) -> Self {
+        match merge {
+            Merge::SourceOfTruth => SyncStrategy::SourceOfTruth,
+            Merge::Union => SyncStrategy::Union,
+            Merge::Intersection => SyncStrategy::Intersection,
+            Merge::PreferNewest => SyncStrategy::PreferNewest,
+            Merge::ThreeWay => SyncStrategy::ThreeWay,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// List saved profiles, marking the one that matches the current setup
+    List,
+    /// Save where every server is enabled right now
+    Save { name: String },
+    /// Enable and disable servers to match a profile
+    Apply {
+        name: String,
+        /// Show the planned changes without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    Delete { name: String },
+}
+
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// List the templates and their parameters
+    List,
+    /// Show the directories templates are loaded from, or replace them
+    Dirs {
+        /// Directories in priority order, lowest first
+        dirs: Vec<String>,
+        /// Load templates from no directories, only the built-ins
+        #[arg(long, conflicts_with = "dirs")]
+        clear: bool,
+    },
+    /// Add a server made from a template, e.g. `template add github --set token=mcphub-secret://github`
+    Add {
+        id: String,
+        /// Server name (default: the template's id)
+        #[arg(long)]
+        name: Option<String>,
+        /// Parameter value as NAME=VALUE
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        values: Vec<String>,
+        /// Clients to add the server to (default: all)
+        #[arg(long = "to", value_enum)]
+        targets: Vec<Client>,
+    },
+    /// List servers made from a template that has a newer version
+    Upgrades,
+    /// Re-render servers from the newer version of their template, keeping their values
+    Upgrade {
+        /// Servers to upgrade (default: every server with an upgrade)
+        servers: Vec<String>,
+        /// Value for a parameter the new version needs, as SERVER.PARAM=VALUE
+        #[arg(long = "set", value_name = "SERVER.PARAM=VALUE")]
+        values: Vec<String>,
+        /// Show the planned changes without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum MarketAction {
+    /// Load the registry index from a file or URL, or reload it from where it came from
+    Refresh { source: Option<String> },
+    /// Search the downloaded index
+    Search {
+        /// Text in the name, title or description
+        text: Option<String>,
+        #[arg(long)]
+        tag: Option<String>,
+        /// Text in a package identifier
+        #[arg(long)]
+        package: Option<String>,
+    },
+    /// Show a server's packages, remotes and the values installing it needs
+    Show {
+        server: String,
+        /// Package identifier or remote URL (default: the first one that can be installed)
+        #[arg(long)]
+        package: Option<String>,
+    },
+    /// Add a server from the index, e.g. `market install io.github.acme/weather --set API_KEY=...`
+    Install {
+        server: String,
+        /// Server name (default: the registry name after the namespace)
+        #[arg(long)]
+        name: Option<String>,
+        /// Package identifier or remote URL (default: the first one that can be installed)
+        #[arg(long)]
+        package: Option<String>,
+        /// Value as NAME=VALUE
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        values: Vec<String>,
+        /// Clients to add the server to (default: all)
+        #[arg(long = "to", value_enum)]
+        targets: Vec<Client>,
+    },
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List every server and where it is enabled
+    List {
+        /// Only servers with this tag (repeat for several)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        #[arg(long)]
+        group: Option<String>,
+        #[arg(long)]
+        owner: Option<String>,
+        /// Only servers mentioning this in their name, tags, description or notes
+        #[arg(long)]
+        search: Option<String>,
+    },
+    /// Show one server's definition, with env and header values masked
+    Show {
+        name: String,
+        /// Print env and header values as they are
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Add or replace a server, e.g. `add github --env TOKEN=... -- npx -y @modelcontextprotocol/server-github`
+    Add {
+        name: String,
+        /// Clients to add the server to (default: all)
+        #[arg(long = "to", value_enum)]
+        targets: Vec<Client>,
+        /// Environment variable as KEY=VALUE
+        #[arg(long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+        /// URL of a remote server, instead of a command
+        #[arg(long, conflicts_with = "command")]
+        url: Option<String>,
+        /// HTTP header for a remote server as KEY=VALUE
+        #[arg(long = "header", value_name = "KEY=VALUE", requires = "url")]
+        headers: Vec<String>,
+        /// The server command and its arguments
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+    /// Rename a server in every client that has it, keeping its history and settings
+    Rename { name: String, new_name: String },
+    /// Set a server's tags, group and notes; an empty value clears a field
+    Meta {
+        name: String,
+        /// Replaces the server's tags (repeat for several)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        #[arg(long)]
+        group: Option<String>,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long)]
+        owner: Option<String>,
+        #[arg(long)]
+        source_url: Option<String>,
+        #[arg(long)]
+        notes: Option<String>,
+    },
+    /// Remove a server
+    Remove {
+        name: String,
+        /// Clients to remove it from (default: all)
+        #[arg(long = "from", value_enum)]
+        targets: Vec<Client>,
+    },
+    /// Enable a server in a client, copying its definition from another client
+    Enable {
+        name: String,
+        #[arg(value_enum)]
+        client: Client,
+    },
+    /// Disable a server in a client
+    Disable {
+        name: String,
+        #[arg(value_enum)]
+        client: Client,
+    },
+    /// Copy servers from one client to the others
+    Sync {
+        #[arg(long, value_enum)]
+        from: Client,
+        /// Only sync this server
+        #[arg(long)]
+        server: Option<String>,
+        /// Clients to sync to (default: all others)
+        #[arg(long = "to", value_enum)]
+        targets: Vec<Client>,
+        /// How to merge when syncing everything
+        #[arg(long, value_enum, conflicts_with = "server")]
+        strategy: Option<Merge>,
+        /// Show the planned changes without writing anything
+        #[arg(long, conflicts_with = "server")]
+        dry_run: bool,
+        /// Settle a three-way conflict with one client's copy, as SERVER=CLIENT
+        #[arg(long = "resolve", conflicts_with = "server")]
+        resolutions: Vec<String>,
+    },
+    /// Keep a client's own value for fields of a server when syncing, e.g. `alwaysAllow` or `env.LOG_LEVEL`
+    Pin {
+        name: String,
+        #[arg(value_enum)]
+        client: Client,
+        #[arg(required = true)]
+        fields: Vec<String>,
+    },
+    /// Let syncs update pinned fields again (default: all of them)
+    Unpin {
+        name: String,
+        #[arg(value_enum)]
+        client: Client,
+        fields: Vec<String>,
+    },
+    /// Show clients whose copy of a server differs from its canonical definition
+    Drift,
+    /// Save, list and switch between sets of enabled servers
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Add servers from the template catalog
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Find and install servers from an MCP registry index
+    Market {
+        #[command(subcommand)]
+        action: MarketAction,
+    },
+    /// Compare the servers of two clients
+    Diff {
+        #[arg(value_enum)]
+        a: Client,
+        #[arg(value_enum)]
+        b: Client,
+    },
+    /// Start a server briefly to check that it comes up
+    Test { name: String },
+    /// Export a client's servers, or all servers as a bundle
+    Export {
+        /// Client to export (not needed with --bundle)
+        #[arg(long, value_enum, required_unless_present = "bundle")]
+        from: Option<Client>,
+        /// Write a .mcphub.json bundle of all servers instead
+        #[arg(long)]
+        bundle: bool,
+        /// Replace likely secrets with placeholders
+        #[arg(long)]
+        redact: bool,
+        /// Sign the bundle with the local signing key
+        #[arg(long, requires = "bundle")]
+        sign: bool,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import servers from a file (server map, client config or bundle)
+    Import {
+        file: PathBuf,
+        /// Clients to import into (default: all, or where a bundle enables each server)
+        #[arg(long = "to", value_enum)]
+        targets: Vec<Client>,
+        /// What to do when a server name is already taken
+        #[arg(long, value_enum, default_value = "skip")]
+        strategy: Strategy,
+        /// Value for a redacted placeholder as NAME=VALUE
+        #[arg(long = "value", value_name = "NAME=VALUE")]
+        values: Vec<String>,
+        /// Trust a bundle signer seen for the first time
+        #[arg(long)]
+        trust_signer: bool,
+    },
+    /// Back up every client config
+    Backup,
+}
+
+fn targets_or_all(targets: &[Client]) -> Vec<ConfigType> {
+    if targets.is_empty() {
+        ConfigType::all()
+    } else {
+        targets.iter().map(|&c| c.into()).collect()
+    }
+}
+
+fn parse_pairs(pairs: &[String]) -> Result<HashMap<String, String>> {
+    pairs
+        .iter()
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .ok_or_else(|| AppError::InvalidArgument(format!("Expected KEY=VALUE, got: {}", pair)))
+        })
+        .collect()
+}
+
+fn parse_upgrade_values(pairs: &[String]) -> Result<HashMap<String, HashMap<String, String>>> {
+    let mut values: HashMap<String, HashMap<String, String>> = HashMap::new();
+    for (key, value) in parse_pairs(pairs)? {
+        let (server, parameter) = key
+            .rsplit_once('.')
+            .ok_or_else(|| AppError::InvalidArgument(format!("Expected SERVER.PARAM=VALUE, got: {}", key)))?;
+        values.entry(server.to_string()).or_default().insert(parameter.to_string(), value);
+    }
+    Ok(values)
+}
+
+fn parse_resolutions(pairs: &[String]) -> Result<HashMap<String, ConfigType>> {
+    parse_pairs(pairs)?
+        .into_iter()
+        .map(|(server, client)| {
+            let client = Client::from_str(&client, true)
+                .map_err(|_| AppError::InvalidArgument(format!("Unknown client: {}", client)))?;
+            Ok((server, ConfigType::from(client)))
+        })
+        .collect()
+}
+
+fn find_server(backend: &Backend, name: &str) -> Result<ManagedServer> {
+    backend
+        .call::<Vec<ManagedServer>>("get_managed_servers", json!({}))?
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| AppError::ServerNotFound(name.to_string()))
+}
+
+fn print_changes(plan: &ChangePlan) {
+    for change in &plan.changes {
+        println!(
+            "{:<8} {:<30} {:<16} {}",
+            format!("{:?}", change.action).to_lowercase(),
+            change.server,
+            change.config_type.display_name(),
+            change.reason
+        );
+    }
+}
+
+fn sync_rule(backend: &Backend, name: &str) -> Result<SyncRule> {
+    let state: AppState = backend.call("get_app_state", json!({}))?;
+    Ok(state.sync_rules.get(name).cloned().unwrap_or_default())
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn enabled_marks(server: &ManagedServer) -> String {
+    ConfigType::all()
+        .iter()
+        .map(|ct| {
+            let enabled = server.systems.get(ct.system_key()).is_some_and(|s| s.enabled);
+            if enabled {
+                ct.short_name().to_string()
+            } else {
+                "--".to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn show_value(value: &Option<serde_json::Value>) -> String {
+    match value {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn execute(cli: Cli) -> Result<()> {
+    let backend = Backend::connect();
+
+    match cli.command {
+        Commands::List {
+            tags,
+            group,
+            owner,
+            search,
+        } => {
+            let query = ServerQuery {
+                tags,
+                group,
+                owner,
+                text: search,
+            };
+            let servers: Vec<ManagedServer> = backend.call("get_managed_servers", json!({ "query": query }))?;
+            if cli.json {
+                return print_json(&servers);
+            }
+            for server in &servers {
+                let health = format!("{:?}", server.health);
+                let line = format!("{:<30} {}  {:<9} {}", server.name, enabled_marks(server), health, server.metadata.tags.join(", "));
+                println!("{}", line.trim_end());
+            }
+        }
+
+        Commands::Show { name, reveal } => {
+            let mut server = find_server(&backend, &name)?;
+            if !reveal {
+                for value in server.env.values_mut().chain(server.headers.values_mut()) {
+                    *value = diff::masked(value).to_string();
+                }
+            }
+            if cli.json {
+                return print_json(&server);
+            }
+            println!("{}", server.name);
+            match server.url {
+                Some(ref url) => println!("  url:     {}", url),
+                None => println!("  command: {} {}", server.command, server.args.join(" ")),
+            }
+            let mut env: Vec<_> = server.env.iter().collect();
+            env.sort();
+            for (key, value) in env {
+                println!("  env:     {}={}", key, value);
+            }
+            println!("  enabled: {}", enabled_marks(&server));
+            for ct in ConfigType::all() {
+                if let Some(status) = server.systems.get(ct.system_key()) {
+                    if status.drift == DriftStatus::Drifted {
+                        println!("  drifted: {} {}", ct.short_name(), status.drifted_fields.join(", "));
+                    }
+                }
+            }
+            for (config_type, fields) in sync_rule(&backend, &name)?.pinned {
+                println!("  pinned:  {} {}", config_type.short_name(), fields.join(", "));
+            }
+            let metadata = &server.metadata;
+            if !metadata.tags.is_empty() {
+                println!("  tags:    {}", metadata.tags.join(", "));
+            }
+            let fields = [
+                ("group:  ", &metadata.group),
+                ("about:  ", &metadata.description),
+                ("owner:  ", &metadata.owner),
+                ("source: ", &metadata.source_url),
+                ("notes:  ", &metadata.notes),
+            ];
+            for (label, value) in fields {
+                if let Some(value) = value {
+                    println!("  {} {}", label, value);
+                }
+            }
+            println!("  health:  {:?}", server.health);
+            if let Some(ref error) = server.error_message {
+                println!("  error:   {}", error);
+            }
+        }
+
+        Commands::Add {
+            name,
+            targets,
+            env,
+            url,
+            headers,
+            command,
+        } => {
+            let mut command = command.into_iter();
+            let server = MCPServer {
+                command: command.next().unwrap_or_default(),
+                args: command.collect(),
+                env: parse_pairs(&env)?,
+                headers: parse_pairs(&headers)?,
+                url,
+                ..Default::default()
+            };
+            if server.command.is_empty() && server.url.is_none() {
+                return Err(AppError::InvalidArgument("Give a command after `--` or a --url".to_string()));
+            }
+            let request = SaveServerRequest {
+                name: name.clone(),
+                server,
+                targets: targets_or_all(&targets),
+                original_name: None,
+                metadata: None,
+            };
+            backend.call::<()>("save_server", json!({ "request": request }))?;
+            if cli.json {
+                return print_json(&json!({ "action": "added", "server": name, "clients": request.targets }));
+            }
+            println!("Added {}", name);
+        }
+
+        Commands::Rename { name, new_name } => {
+            backend.call::<()>("rename_server", json!({ "name": name, "newName": new_name }))?;
+            if cli.json {
+                return print_json(&json!({ "action": "renamed", "server": name, "newName": new_name }));
+            }
+            println!("Renamed {} to {}", name, new_name);
+        }
+
+        Commands::Meta {
+            name,
+            tags,
+            group,
+            description,
+            owner,
+            source_url,
+            notes,
+        } => {
+            let current = find_server(&backend, &name)?.metadata;
+            let metadata = ServerMetadata {
+                tags: if tags.is_empty() { current.tags } else { tags },
+                group: group.or(current.group),
+                description: description.or(current.description),
+                owner: owner.or(current.owner),
+                source_url: source_url.or(current.source_url),
+                notes: notes.or(current.notes),
+            };
+            backend.call::<()>("set_server_metadata", json!({ "name": name, "metadata": metadata }))?;
+            if cli.json {
+                return print_json(&json!({ "action": "updated", "server": name, "metadata": metadata }));
+            }
+            println!("Updated {}", name);
+        }
+
+        Commands::Remove { name, targets } => {
+            let clients = targets_or_all(&targets);
+            backend.call::<()>("delete_server", json!({ "name": name, "targets": clients }))?;
+            if cli.json {
+                return print_json(&json!({ "action": "removed", "server": name, "clients": clients }));
+            }
+            println!("Removed {}", name);
+        }
+
+        Commands::Enable { name, client } => {
+            let request = SetEnabledRequest {
+                name: name.clone(),
+                config_type: client.into(),
+                enabled: true,
+            };
+            backend.call::<()>("set_server_enabled", json!({ "request": request }))?;
+            if cli.json {
+                return print_json(&json!({ "action": "enabled", "server": name, "client": request.config_type }));
+            }
+            println!("Enabled {} in {}", name, ConfigType::from(client).display_name());
+        }
+
+        Commands::Disable { name, client } => {
+            let request = SetEnabledRequest {
+                name: name.clone(),
+                config_type: client.into(),
+                enabled: false,
+            };
+            backend.call::<()>("set_server_enabled", json!({ "request": request }))?;
+            if cli.json {
+                return print_json(&json!({ "action": "disabled", "server": name, "client": request.config_type }));
+            }
+            println!("Disabled {} in {}", name, ConfigType::from(client).display_name());
+        }
+
+        Commands::Sync {
+            from,
+            server,
+            targets,
+            strategy,
+            dry_run,
+            resolutions,
+        } => {
+            let source = ConfigType::from(from);
+            let plan: ChangePlan = match server {
+                Some(name) => {
+                    let request = SyncRequest {
+                        name,
+                        source,
+                        targets: targets_or_all(&targets).into_iter().filter(|t| *t != source).collect(),
+                    };
+                    backend.call::<()>("sync_server", json!({ "request": request }))?;
+                    if cli.json {
+                        return print_json(&json!({
+                            "action": "synced",
+                            "server": request.name,
+                            "source": source,
+                            "clients": request.targets,
+                        }));
+                    }
+                    println!("Synced from {}", source.display_name());
+                    return Ok(());
+                }
+                None if targets.is_empty() => {
+                    let method = if dry_run { "preview_sync" } else { "sync_all_servers" };
+                    let strategy = strategy.map(SyncStrategy::from).unwrap_or_default();
+                    let resolutions = parse_resolutions(&resolutions)?;
+                    backend.call(
+                        method,
+                        json!({ "source": source, "strategy": strategy, "resolutions": resolutions }),
+                    )?
+                }
+                None => {
+                    return Err(AppError::InvalidArgument(
+                        "--to needs --server; syncing everything always goes to all clients".to_string(),
+                    ))
+                }
+            };
+
+            if cli.json {
+                return print_json(&plan);
+            }
+            print_changes(&plan);
+            for conflict in &plan.conflicts {
+                let clients: Vec<&str> = conflict.versions.iter().map(|v| v.config_type.display_name()).collect();
+                println!("{:<8} {:<30} {:<16} {}", "conflict", conflict.server, "", clients.join(" vs "));
+            }
+            if plan.is_empty() && plan.conflicts.is_empty() {
+                println!("Already in sync");
+            } else if !dry_run && matches!(strategy, Some(Merge::ThreeWay)) {
+                println!("Synced");
+            } else if !dry_run {
+                println!("Synced from {}", source.display_name());
+            }
+            if !plan.conflicts.is_empty() {
+                println!("Resolve conflicts with --resolve SERVER=CLIENT");
+            }
+        }
+
+        Commands::Profile { action } => match action {
+            ProfileAction::List => {
+                let state: AppState = backend.call("get_app_state", json!({}))?;
+                let active: Option<String> = backend.call("get_active_profile", json!({}))?;
+                if cli.json {
+                    return print_json(&json!({ "profiles": state.profiles, "active": active }));
+                }
+                let mut names: Vec<&String> = state.profiles.keys().collect();
+                names.sort();
+                for name in names {
+                    let mark = if active.as_ref() == Some(name) { "*" } else { " " };
+                    println!("{} {:<30} {} servers", mark, name, state.profiles[name].enabled.len());
+                }
+            }
+            ProfileAction::Save { name } => {
+                backend.call::<()>("save_profile", json!({ "name": name }))?;
+                if cli.json {
+                    return print_json(&json!({ "action": "saved", "profile": name }));
+                }
+                println!("Saved profile {}", name);
+            }
+            ProfileAction::Apply { name, dry_run } => {
+                let method = if dry_run { "preview_profile" } else { "apply_profile" };
+                let plan: ChangePlan = backend.call(method, json!({ "name": name }))?;
+                if cli.json {
+                    return print_json(&plan);
+                }
+                print_changes(&plan);
+                if plan.is_empty() {
+                    println!("Already on profile {}", name);
+                } else if !dry_run {
+                    println!("Switched to profile {}", name);
+                }
+            }
+            ProfileAction::Delete { name } => {
+                backend.call::<()>("delete_profile", json!({ "name": name }))?;
+                if cli.json {
+                    return print_json(&json!({ "action": "deleted", "profile": name }));
+                }
+                println!("Deleted profile {}", name);
+            }
+        },
+
+        Commands::Template { action } => match action {
+            TemplateAction::List => {
+                let catalog: Catalog = backend.call("list_templates", json!({}))?;
+                if cli.json {
+                    return print_json(&catalog);
+                }
+                for template in &catalog.templates {
+                    let source = match template.source {
+                        Some(ref path) if template.overrides_builtin => format!("{}, overrides built-in", path),
+                        Some(ref path) => path.clone(),
+                        None => "built-in".to_string(),
+                    };
+                    println!("{:<16} {} [{}]", template.id, template.description, source);
+                    for parameter in &template.parameters {
+                        let kind = format!("{:?}", parameter.kind).to_lowercase();
+                        let optional = if parameter.required { "" } else { ", optional" };
+                        println!("  {:<20} {} ({}{})", parameter.name, parameter.label, kind, optional);
+                    }
+                }
+                for problem in &catalog.problems {
+                    eprintln!("Skipped {}: {}", problem.path, problem.message);
+                }
+            }
+            TemplateAction::Dirs { dirs, clear } => {
+                if dirs.is_empty() && !clear {
+                    let state: AppState = backend.call("get_app_state", json!({}))?;
+                    if cli.json {
+                        return print_json(&state.template_dirs);
+                    }
+                    for dir in &state.template_dirs {
+                        println!("{}", dir);
+                    }
+                    return Ok(());
+                }
+                backend.call::<()>("set_template_dirs", json!({ "dirs": dirs }))?;
+                if cli.json {
+                    return print_json(&json!({ "action": "updated", "templateDirs": dirs }));
+                }
+                println!("Loading templates from {} directories", dirs.len());
+            }
+            TemplateAction::Add {
+                id,
+                name,
+                values,
+                targets,
+            } => {
+                let values = parse_pairs(&values)?;
+                let issues: Vec<ParameterIssue> =
+                    backend.call("validate_template", json!({ "id": id, "values": values }))?;
+                if !issues.is_empty() {
+                    if cli.json {
+                        return print_json(&issues);
+                    }
+                    for issue in &issues {
+                        eprintln!("{}: {}", issue.parameter, issue.message);
+                    }
+                    return Err(AppError::InvalidArgument(format!("Template {} needs different values", id)));
+                }
+                let request = InstantiateRequest {
+                    name: name.clone(),
+                    template_id: id.clone(),
+                    values,
+                    targets: targets_or_all(&targets),
+                };
+                let server: MCPServer = backend.call("instantiate_template", json!({ "request": request }))?;
+                if cli.json {
+                    return print_json(&server);
+                }
+                println!("Added {}", name.unwrap_or(id));
+            }
+            TemplateAction::Upgrades => {
+                let upgrades: Vec<TemplateUpgrade> = backend.call("get_template_upgrades", json!({}))?;
+                if cli.json {
+                    return print_json(&upgrades);
+                }
+                for upgrade in &upgrades {
+                    let versions = format!("v{} -> v{}", upgrade.from_version, upgrade.to_version);
+                    println!("{:<30} {:<16} {}", upgrade.server, upgrade.template_id, versions);
+                    for issue in &upgrade.issues {
+                        println!("  {:<20} {}", issue.parameter, issue.message);
+                    }
+                }
+            }
+            TemplateAction::Upgrade {
+                servers,
+                values,
+                dry_run,
+            } => {
+                let request = UpgradeRequest {
+                    servers,
+                    values: parse_upgrade_values(&values)?,
+                };
+                let method = if dry_run { "preview_template_upgrade" } else { "apply_template_upgrade" };
+                let plan: ChangePlan = backend.call(method, json!({ "request": request }))?;
+                if cli.json {
+                    return print_json(&plan);
+                }
+                print_changes(&plan);
+                if plan.is_empty() {
+                    println!("Nothing to change in client configs");
+                } else if !dry_run {
+                    println!("Upgraded");
+                }
+            }
+        },
+
+        Commands::Market { action } => match action {
+            MarketAction::Refresh { source } => {
+                let index: MarketplaceIndex = backend.call("refresh_marketplace", json!({ "source": source }))?;
+                if cli.json {
+                    return print_json(&index);
+                }
+                println!("Loaded {} servers from {}", index.servers.len(), index.source.unwrap_or_default());
+            }
+            MarketAction::Search { text, tag, package } => {
+                let query = MarketplaceQuery {
+                    name: text,
+                    tag,
+                    package,
+                };
+                let servers: Vec<MarketplaceServer> = backend.call("search_marketplace", json!({ "query": query }))?;
+                if cli.json {
+                    return print_json(&servers);
+                }
+                for server in &servers {
+                    let version = server.version.as_deref().unwrap_or("");
+                    println!("{:<40} {:<10} {}", server.name, version, server.description);
+                    if !server.tags.is_empty() {
+                        println!("  tags: {}", server.tags.join(", "));
+                    }
+                }
+            }
+            MarketAction::Show { server, package } => {
+                let template: ServerTemplate =
+                    backend.call("get_marketplace_template", json!({ "server": server, "package": package }))?;
+                if cli.json {
+                    return print_json(&template);
+                }
+                println!("{} - {}", template.name, template.description);
+                match template.server.url {
+                    Some(ref url) => println!("  url:     {}", url),
+                    None => println!("  command: {} {}", template.server.command, template.server.args.join(" ")),
+                }
+                for parameter in &template.parameters {
+                    let kind = format!("{:?}", parameter.kind).to_lowercase();
+                    let optional = if parameter.required { "" } else { ", optional" };
+                    let about = parameter.description.as_deref().unwrap_or("");
+                    println!("  {:<20} ({}{}) {}", parameter.name, kind, optional, about);
+                }
+            }
+            MarketAction::Install {
+                server,
+                name,
+                package,
+                values,
+                targets,
+            } => {
+                let request = InstallRequest {
+                    server: server.clone(),
+                    name: name.clone(),
+                    package,
+                    values: parse_pairs(&values)?,
+                    targets: targets_or_all(&targets),
+                };
+                let installed: MCPServer = backend.call("install_from_marketplace", json!({ "request": request }))?;
+                if cli.json {
+                    return print_json(&installed);
+                }
+                println!("Added {}", name.unwrap_or_else(|| server.rsplit('/').next().unwrap_or(&server).to_string()));
+            }
+        },
+
+        Commands::Pin { name, client, fields } => {
+            let mut rule = sync_rule(&backend, &name)?;
+            let pinned = rule.pinned.entry(client.into()).or_default();
+            for field in fields {
+                if !pinned.contains(&field) {
+                    pinned.push(field);
+                }
+            }
+            let summary = pinned.join(", ");
+            backend.call::<()>("set_sync_rule", json!({ "name": name, "rule": rule }))?;
+            if cli.json {
+                return print_json(&json!({ "action": "pinned", "server": name, "rule": rule }));
+            }
+            println!("Pinned in {}: {}", ConfigType::from(client).display_name(), summary);
+        }
+
+        Commands::Unpin { name, client, fields } => {
+            let mut rule = sync_rule(&backend, &name)?;
+            let config_type = ConfigType::from(client);
+            if let Some(pinned) = rule.pinned.get_mut(&config_type) {
+                pinned.retain(|field| !fields.is_empty() && !fields.contains(field));
+                if pinned.is_empty() {
+                    rule.pinned.remove(&config_type);
+                }
+            }
+            backend.call::<()>("set_sync_rule", json!({ "name": name, "rule": rule }))?;
+            if cli.json {
+                return print_json(&json!({ "action": "unpinned", "server": name, "rule": rule }));
+            }
+            println!("Unpinned {} in {}", name, config_type.display_name());
+        }
+
+        Commands::Drift => {
+            let report: DriftReport = backend.call("get_drift_report", json!({}))?;
+            if cli.json {
+                return print_json(&report);
+            }
+            if report.servers.is_empty() {
+                println!("No drift");
+            }
+            for entry in &report.servers {
+                println!("{}", entry.server);
+                for client in &entry.clients {
+                    let status = match (client.status, client.enabled) {
+                        (DriftStatus::InSync, _) => "in sync",
+                        (DriftStatus::Drifted, true) => "drifted",
+                        (DriftStatus::Drifted, false) => "drifted (disabled)",
+                    };
+                    let fields: Vec<&str> = client.fields.iter().map(|f| f.field.as_str()).collect();
+                    let line = format!("  {:<16} {:<20} {}", client.config_type.display_name(), status, fields.join(", "));
+                    println!("{}", line.trim_end());
+                }
+                println!("  {}", entry.reason);
+                if let Some(ref sync) = entry.suggestion {
+                    // With no targets, syncing to the source itself just makes its copy canonical
+                    let targets = if sync.targets.is_empty() { vec![sync.source] } else { sync.targets.clone() };
+                    let to: Vec<String> = targets.iter().map(|t| format!("--to {}", t.short_name().to_lowercase())).collect();
+                    println!(
+                        "  mcphub-cli sync --from {} --server {} {}",
+                        sync.source.short_name().to_lowercase(),
+                        entry.server,
+                        to.join(" ")
+                    );
+                }
+            }
+        }
+
+        Commands::Diff { a, b } => {
+            let result: ConfigDiff = backend.call(
+                "diff_configs",
+                json!({ "a": ConfigType::from(a), "b": ConfigType::from(b) }),
+            )?;
+            if cli.json {
+                return print_json(&result);
+            }
+            for server in &result.servers {
+                let mark = match server.status {
+                    DiffStatus::OnlyInA => "<",
+                    DiffStatus::OnlyInB => ">",
+                    DiffStatus::Differing => "~",
+                    DiffStatus::Identical => continue,
+                };
+                println!("{} {}", mark, server.name);
+                for change in &server.changes {
+                    println!("    {}: {} -> {}", change.field, show_value(&change.a), show_value(&change.b));
+                }
+            }
+        }
+
+        Commands::Test { name } => {
+            let server = find_server(&backend, &name)?.to_server();
+            let result: TestResult =
+                backend.call("test_server_connection", json!({ "name": name, "server": server }))?;
+
+            if cli.json {
+                print_json(&result)?;
+            } else {
+                // A failure's message is the error this returns
+                if result.success {
+                    println!("{}", result.message);
+                }
+                if let Some(ref output) = result.output {
+                    println!("{}", output);
+                }
+            }
+            if !result.success {
+                return Err(AppError::ProcessError(result.message));
+            }
+        }
+
+        Commands::Export {
+            from,
+            bundle,
+            redact,
+            sign,
+            output,
+        } => {
+            let json: String = match from {
+                _ if bundle => {
+                    let request = ExportBundleRequest {
+                        redact,
+                        sign,
+                        ..Default::default()
+                    };
+                    backend.call("export_bundle", json!({ "request": request }))?
+                }
+                Some(client) => backend.call(
+                    "export_config",
+                    json!({ "source": ConfigType::from(client), "redact": redact }),
+                )?,
+                None => return Err(AppError::InvalidArgument("--from is required without --bundle".to_string())),
+            };
+            match output {
+                Some(path) => std::fs::write(path, json)?,
+                None => println!("{}", json),
+            }
+        }
+
+        Commands::Import {
+            file,
+            targets,
+            strategy,
+            values,
+            trust_signer,
+        } => {
+            let json = std::fs::read_to_string(&file)?;
+            let options = ImportOptions {
+                default_strategy: Some(strategy.into()),
+                values: parse_pairs(&values)?,
+                source_modified: std::fs::metadata(&file).and_then(|m| m.modified()).ok().map(Into::into),
+                ..Default::default()
+            };
+
+            let is_bundle = serde_json::from_str::<serde_json::Value>(&json)?
+                .get("schemaVersion")
+                .is_some();
+            let report = if is_bundle {
+                let request = ImportBundleRequest {
+                    json,
+                    targets: (!targets.is_empty()).then(|| targets_or_all(&targets)),
+                    trust_signer,
+                    options: ImportOptions {
+                        source_modified: None,
+                        ..options
+                    },
+                };
+                let report: BundleImportReport = backend.call("import_bundle", json!({ "request": request }))?;
+                if !cli.json {
+                    eprintln!("{}", report.verification.message);
+                    for id in &report.left_out_templates {
+                        eprintln!("Left out template {}: only a trusted signer can replace it", id);
+                    }
+                }
+                report.report
+            } else {
+                let request = ImportRequest {
+                    json,
+                    targets: targets_or_all(&targets),
+                    options,
+                };
+                backend.call::<ImportReport>("import_config", json!({ "request": request }))?
+            };
+
+            if cli.json {
+                return print_json(&report);
+            }
+            for change in &report.changes {
+                println!(
+                    "{:<12} {:<30} {}",
+                    format!("{:?}", change.action).to_lowercase(),
+                    change.written_as.as_ref().unwrap_or(&change.name),
+                    change.config_type.display_name()
+                );
+            }
+        }
+
+        Commands::Backup => {
+            let backups: Vec<String> = backend.call("backup_configs", json!({}))?;
+            if cli.json {
+                return print_json(&backups);
+            }
+            for path in &backups {
+                println!("{}", path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse `args`, program name first, and run the command.
+pub fn run(args: &[String]) -> Result<()> {
+    execute(Cli::parse_from(args))
+}
@@ -1,329 +1,384 @@
-use crate::config::{ConfigManager, ConfigType, HealthStatus, MCPConfig, MCPServer, ManagedServer, AppState};
+use crate::bundle::{BundleImportReport, BundlePreview, ExportBundleRequest, ImportBundleRequest};
+use crate::config::{AppState, ConfigType, MCPConfig, MCPServer, ManagedServer};
+use crate::diff::ConfigDiff;
+use crate::drift::DriftReport;
 use crate::error::{AppError, Result};
-use serde::{Deserialize, Serialize};
+use crate::health::TestResult;
+use crate::hub::{Backend, RecordingRequest, SaveServerRequest, SetEnabledRequest, SyncRequest};
+use crate::import::{ImportPreview, ImportReport, ImportRequest};
+use crate::interpolate::InterpolationIssue;
+use crate::marketplace::{InstallRequest, MarketplaceIndex, MarketplaceQuery, MarketplaceServer};
+use crate::metadata::{ServerMetadata, ServerQuery};
+use crate::monitor::ResourceSample;
+use crate::plan::ChangePlan;
+use crate::profiles::Profile;
+use crate::recorder::{MessageFilter, RecordedMessage, RecordingSession};
+use crate::redact::Redaction;
+use crate::secrets::SecretInfo;
+use crate::signing::{BundlePolicy, SigningIdentity, TrustedKey, Verification};
+use crate::supervisor::RunningServer;
+use crate::sync::{SyncRule, SyncStrategy};
+use crate::templates::{
+    Catalog, InstantiateRequest, ParameterIssue, ServerTemplate, TemplateUpgrade, UpgradeRequest,
+};
+use serde_json::json;
 use std::collections::HashMap;
-use std::path::Path;
-use std::sync::Mutex;
-use std::time::Duration;
 use tauri::State;
-use tokio::process::Command;
 
-type ConfigManagerState = Mutex<ConfigManager>;
+// Each command forwards to the hub under its own name, either in-process or
+// through the daemon when one is running; see `hub::Hub::call`.
 
-/// Resolve a command name to its full path by checking common locations
-fn resolve_command(cmd: &str) -> Option<String> {
-    // If it's already an absolute path, check if it exists
-    if cmd.starts_with('/') {
-        if Path::new(cmd).exists() {
-            return Some(cmd.to_string());
-        }
-        return None;
-    }
+#[tauri::command]
+pub fn get_all_configs(backend: State<'_, Backend>) -> Result<Vec<MCPConfig>> {
+    backend.call("get_all_configs", json!({}))
+}
 
-    // Common paths to search (in order of priority)
-    let search_paths = [
-        "/opt/homebrew/bin",
-        "/usr/local/bin",
-        "/usr/bin",
-        "/bin",
-        "/opt/homebrew/sbin",
-        "/usr/local/sbin",
-        "/usr/sbin",
-        "/sbin",
-        // Add home directory paths for tools like nvm, pyenv, etc.
-        &format!("{}/.nvm/versions/node/v22.9.0/bin", std::env::var("HOME").unwrap_or_default()),
-        &format!("{}/.cargo/bin", std::env::var("HOME").unwrap_or_default()),
-        &format!("{}/.local/bin", std::env::var("HOME").unwrap_or_default()),
-        &format!("{}/Library/pnpm", std::env::var("HOME").unwrap_or_default()),
-    ];
+#[tauri::command]
+pub fn get_managed_servers(backend: State<'_, Backend>, query: Option<ServerQuery>) -> Result<Vec<ManagedServer>> {
+    backend.call("get_managed_servers", json!({ "query": query }))
+}
 
-    for path in &search_paths {
-        let full_path = format!("{}/{}", path, cmd);
-        if Path::new(&full_path).exists() {
-            return Some(full_path);
-        }
-    }
+#[tauri::command]
+pub fn save_server(backend: State<'_, Backend>, request: SaveServerRequest) -> Result<()> {
+    backend.call("save_server", json!({ "request": request }))
+}
 
-    None
+#[tauri::command]
+pub fn delete_server(backend: State<'_, Backend>, name: String, targets: Option<Vec<ConfigType>>) -> Result<()> {
+    backend.call("delete_server", json!({ "name": name, "targets": targets }))
 }
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct TestResult {
-    pub success: bool,
-    pub message: String,
-    pub output: Option<String>,
+#[tauri::command]
+pub fn rename_server(backend: State<'_, Backend>, name: String, new_name: String) -> Result<()> {
+    backend.call("rename_server", json!({ "name": name, "newName": new_name }))
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SaveServerRequest {
-    pub name: String,
-    pub server: MCPServer,
-    pub targets: Vec<ConfigType>,
-    pub original_name: Option<String>,
+#[tauri::command]
+pub fn set_server_metadata(backend: State<'_, Backend>, name: String, metadata: ServerMetadata) -> Result<()> {
+    backend.call("set_server_metadata", json!({ "name": name, "metadata": metadata }))
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SetEnabledRequest {
-    pub name: String,
-    pub config_type: ConfigType,
-    pub enabled: bool,
+#[tauri::command]
+pub fn set_server_enabled(backend: State<'_, Backend>, request: SetEnabledRequest) -> Result<()> {
+    backend.call("set_server_enabled", json!({ "request": request }))
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SyncRequest {
-    pub name: String,
-    pub source: ConfigType,
-    pub targets: Vec<ConfigType>,
+#[tauri::command]
+pub async fn test_server_connection(
+    backend: State<'_, Backend>,
+    name: String,
+    server: MCPServer,
+) -> Result<TestResult> {
+    // The test takes a moment, so keep it off the async runtime's threads
+    let backend = backend.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        backend.call("test_server_connection", json!({ "name": name, "server": server }))
+    })
+    .await
+    .map_err(|e| AppError::ProcessError(e.to_string()))?
 }
 
 #[tauri::command]
-pub fn get_all_configs(state: State<'_, ConfigManagerState>) -> Result<Vec<MCPConfig>> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
+pub fn check_variables(backend: State<'_, Backend>, request: SaveServerRequest) -> Result<Vec<InterpolationIssue>> {
+    backend.call("check_variables", json!({ "request": request }))
+}
 
-    ConfigType::all()
-        .into_iter()
-        .map(|ct| manager.read_config(ct))
-        .collect()
+#[tauri::command]
+pub fn sync_server(backend: State<'_, Backend>, request: SyncRequest) -> Result<()> {
+    backend.call("sync_server", json!({ "request": request }))
 }
 
 #[tauri::command]
-pub fn get_managed_servers(state: State<'_, ConfigManagerState>) -> Result<Vec<ManagedServer>> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-    manager.get_managed_servers()
+pub fn sync_all_servers(
+    backend: State<'_, Backend>,
+    source: ConfigType,
+    strategy: Option<SyncStrategy>,
+    resolutions: Option<HashMap<String, ConfigType>>,
+) -> Result<ChangePlan> {
+    backend.call(
+        "sync_all_servers",
+        json!({ "source": source, "strategy": strategy, "resolutions": resolutions }),
+    )
 }
 
 #[tauri::command]
-pub fn save_server(state: State<'_, ConfigManagerState>, request: SaveServerRequest) -> Result<()> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
+pub fn preview_sync(
+    backend: State<'_, Backend>,
+    source: ConfigType,
+    strategy: Option<SyncStrategy>,
+    resolutions: Option<HashMap<String, ConfigType>>,
+) -> Result<ChangePlan> {
+    backend.call(
+        "preview_sync",
+        json!({ "source": source, "strategy": strategy, "resolutions": resolutions }),
+    )
+}
 
-    // If renaming, remove old server first
-    if let Some(ref old_name) = request.original_name {
-        if old_name != &request.name {
-            manager.remove_server(old_name, &ConfigType::all())?;
-        }
-    }
+#[tauri::command]
+pub fn set_sync_rule(backend: State<'_, Backend>, name: String, rule: SyncRule) -> Result<()> {
+    backend.call("set_sync_rule", json!({ "name": name, "rule": rule }))
+}
 
-    manager.add_server(&request.name, &request.server, &request.targets)
+#[tauri::command]
+pub fn backup_configs(backend: State<'_, Backend>) -> Result<Vec<String>> {
+    backend.call("backup_configs", json!({}))
 }
 
 #[tauri::command]
-pub fn delete_server(state: State<'_, ConfigManagerState>, name: String) -> Result<()> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-    manager.remove_server(&name, &ConfigType::all())
+pub fn get_app_state(backend: State<'_, Backend>) -> Result<AppState> {
+    backend.call("get_app_state", json!({}))
 }
 
 #[tauri::command]
-pub fn set_server_enabled(state: State<'_, ConfigManagerState>, request: SetEnabledRequest) -> Result<()> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
+pub fn diff_configs(backend: State<'_, Backend>, a: ConfigType, b: ConfigType) -> Result<ConfigDiff> {
+    backend.call("diff_configs", json!({ "a": a, "b": b }))
+}
 
-    // Get the server template from another config if enabling
-    let template = if request.enabled {
-        let servers = manager.get_managed_servers()?;
-        servers.iter().find(|s| s.name == request.name).map(|s| MCPServer {
-            command: s.command.clone(),
-            args: s.args.clone(),
-            env: s.env.clone(),
-            always_allow: s.always_allow.clone(),
-        })
-    } else {
-        None
-    };
+#[tauri::command]
+pub fn get_drift_report(backend: State<'_, Backend>) -> Result<DriftReport> {
+    backend.call("get_drift_report", json!({}))
+}
 
-    manager.set_server_enabled(&request.name, request.config_type, request.enabled, template.as_ref())
+#[tauri::command]
+pub fn save_profile(backend: State<'_, Backend>, name: String, profile: Option<Profile>) -> Result<()> {
+    backend.call("save_profile", json!({ "name": name, "profile": profile }))
 }
 
 #[tauri::command]
-pub async fn test_server_connection(
-    state: State<'_, ConfigManagerState>,
-    name: String,
-    server: MCPServer,
-) -> Result<TestResult> {
-    let expanded_command = ConfigManager::expand_path(&server.command);
-    let expanded_args: Vec<String> = server.args.iter().map(|a| ConfigManager::expand_path(a)).collect();
-
-    // Resolve the command to a full path
-    let resolved_command = resolve_command(&expanded_command);
-
-    if resolved_command.is_none() {
-        let mut manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-        manager.update_server_health(&name, HealthStatus::Error, Some(format!("Command not found: {}", expanded_command)))?;
-
-        return Ok(TestResult {
-            success: false,
-            message: format!("Command not found: {}. Make sure it's installed and in your PATH.", expanded_command),
-            output: None,
-        });
-    }
-
-    let command_path = resolved_command.unwrap();
-
-    // Check if args contain paths that don't exist
-    for arg in &expanded_args {
-        if arg.starts_with('/') && !arg.contains("://") {
-            if !std::path::Path::new(arg).exists() {
-                let mut manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-                manager.update_server_health(&name, HealthStatus::Error, Some(format!("Path not found: {}", arg)))?;
-
-                return Ok(TestResult {
-                    success: false,
-                    message: format!("Path not found: {}", arg),
-                    output: None,
-                });
-            }
-        }
-    }
-
-    // Try to start the server briefly
-    let mut cmd = Command::new(&command_path);
-    cmd.args(&expanded_args);
-
-    // Set up a proper PATH so the spawned process can find its dependencies
-    let home = std::env::var("HOME").unwrap_or_default();
-    let path = format!(
-        "/opt/homebrew/bin:/usr/local/bin:/usr/bin:/bin:{}/.cargo/bin:{}/.local/bin:{}/Library/pnpm:{}/.nvm/versions/node/v22.9.0/bin",
-        home, home, home, home
-    );
-    cmd.env("PATH", &path);
-    cmd.env("HOME", &home);
-
-    // Set environment variables from server config
-    for (key, value) in &server.env {
-        cmd.env(key, ConfigManager::expand_path(value));
-    }
-
-    // Capture output
-    cmd.stdout(std::process::Stdio::piped());
-    cmd.stderr(std::process::Stdio::piped());
-
-    let spawn_result = cmd.spawn();
-
-    match spawn_result {
-        Ok(mut child) => {
-            // Give it a moment to start
-            tokio::time::sleep(Duration::from_millis(500)).await;
-
-            // Check if it's still running (good sign) or exited with error
-            match child.try_wait() {
-                Ok(Some(status)) => {
-                    if status.success() {
-                        let mut manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-                        manager.update_server_health(&name, HealthStatus::Healthy, None)?;
-
-                        Ok(TestResult {
-                            success: true,
-                            message: "Server started and exited successfully".to_string(),
-                            output: None,
-                        })
-                    } else {
-                        let output = child.wait_with_output().await.ok();
-                        let stderr = output.map(|o| String::from_utf8_lossy(&o.stderr).to_string());
-
-                        let mut manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-                        manager.update_server_health(&name, HealthStatus::Error, stderr.clone())?;
-
-                        Ok(TestResult {
-                            success: false,
-                            message: format!("Server exited with code: {:?}", status.code()),
-                            output: stderr,
-                        })
-                    }
-                }
-                Ok(None) => {
-                    // Still running - this is good, kill it
-                    child.kill().await.ok();
-
-                    let mut manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-                    manager.update_server_health(&name, HealthStatus::Healthy, None)?;
-
-                    Ok(TestResult {
-                        success: true,
-                        message: "Server started successfully".to_string(),
-                        output: None,
-                    })
-                }
-                Err(e) => {
-                    let mut manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-                    manager.update_server_health(&name, HealthStatus::Error, Some(e.to_string()))?;
-
-                    Ok(TestResult {
-                        success: false,
-                        message: format!("Error checking server status: {}", e),
-                        output: None,
-                    })
-                }
-            }
-        }
-        Err(e) => {
-            let mut manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-            manager.update_server_health(&name, HealthStatus::Error, Some(e.to_string()))?;
-
-            Ok(TestResult {
-                success: false,
-                message: format!("Failed to start server: {}", e),
-                output: None,
-            })
-        }
-    }
-}
-
-#[tauri::command]
-pub fn sync_server(state: State<'_, ConfigManagerState>, request: SyncRequest) -> Result<()> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-    manager.sync_server(&request.name, request.source, &request.targets)
-}
+pub fn delete_profile(backend: State<'_, Backend>, name: String) -> Result<()> {
+    backend.call("delete_profile", json!({ "name": name }))
+}
 
 #[tauri::command]
-pub fn sync_all_servers(state: State<'_, ConfigManagerState>, source: ConfigType) -> Result<()> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-    manager.sync_all(source)
+pub fn preview_profile(backend: State<'_, Backend>, name: String) -> Result<ChangePlan> {
+    backend.call("preview_profile", json!({ "name": name }))
 }
 
-#[tauri::command]
-pub fn backup_configs(state: State<'_, ConfigManagerState>) -> Result<Vec<String>> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-
-    let mut backups = Vec::new();
-    for config_type in ConfigType::all() {
-        if let Ok(path) = manager.backup_config(config_type) {
-            backups.push(path);
-        }
-    }
+#[tauri::command]
+pub fn apply_profile(backend: State<'_, Backend>, name: String) -> Result<ChangePlan> {
+    backend.call("apply_profile", json!({ "name": name }))
+}
 
-    Ok(backups)
+#[tauri::command]
+pub fn get_active_profile(backend: State<'_, Backend>) -> Result<Option<String>> {
+    backend.call("get_active_profile", json!({}))
 }
 
 #[tauri::command]
-pub fn get_app_state(state: State<'_, ConfigManagerState>) -> Result<AppState> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-    Ok(manager.get_state().clone())
+pub fn list_templates(backend: State<'_, Backend>) -> Result<Catalog> {
+    backend.call("list_templates", json!({}))
 }
 
 #[tauri::command]
-pub fn import_config(state: State<'_, ConfigManagerState>, json: String) -> Result<()> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
+pub fn set_template_dirs(backend: State<'_, Backend>, dirs: Vec<String>) -> Result<()> {
+    backend.call("set_template_dirs", json!({ "dirs": dirs }))
+}
 
-    let servers: HashMap<String, MCPServer> = serde_json::from_str(&json)?;
+#[tauri::command]
+pub fn validate_template(
+    backend: State<'_, Backend>,
+    id: String,
+    values: HashMap<String, String>,
+) -> Result<Vec<ParameterIssue>> {
+    backend.call("validate_template", json!({ "id": id, "values": values }))
+}
 
-    // Import to all configs
-    for config_type in ConfigType::all() {
-        let mut config = manager.read_config(config_type)?;
-        for (name, server) in &servers {
-            config.servers.insert(name.clone(), server.clone());
-        }
-        manager.write_config(config_type, &config.servers)?;
-    }
+#[tauri::command]
+pub fn instantiate_template(backend: State<'_, Backend>, request: InstantiateRequest) -> Result<MCPServer> {
+    backend.call("instantiate_template", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn get_template_upgrades(backend: State<'_, Backend>) -> Result<Vec<TemplateUpgrade>> {
+    backend.call("get_template_upgrades", json!({}))
+}
+
+#[tauri::command]
+pub fn preview_template_upgrade(backend: State<'_, Backend>, request: UpgradeRequest) -> Result<ChangePlan> {
+    backend.call("preview_template_upgrade", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn apply_template_upgrade(backend: State<'_, Backend>, request: UpgradeRequest) -> Result<ChangePlan> {
+    backend.call("apply_template_upgrade", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn refresh_marketplace(backend: State<'_, Backend>, source: Option<String>) -> Result<MarketplaceIndex> {
+    backend.call("refresh_marketplace", json!({ "source": source }))
+}
+
+#[tauri::command]
+pub fn search_marketplace(
+    backend: State<'_, Backend>,
+    query: Option<MarketplaceQuery>,
+) -> Result<Vec<MarketplaceServer>> {
+    backend.call("search_marketplace", json!({ "query": query }))
+}
+
+#[tauri::command]
+pub fn get_marketplace_template(
+    backend: State<'_, Backend>,
+    server: String,
+    package: Option<String>,
+) -> Result<ServerTemplate> {
+    backend.call("get_marketplace_template", json!({ "server": server, "package": package }))
+}
+
+#[tauri::command]
+pub fn install_from_marketplace(backend: State<'_, Backend>, request: InstallRequest) -> Result<MCPServer> {
+    backend.call("install_from_marketplace", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn find_import_placeholders(backend: State<'_, Backend>, json: String) -> Result<Vec<String>> {
+    backend.call("find_import_placeholders", json!({ "json": json }))
+}
+
+#[tauri::command]
+pub fn preview_import(backend: State<'_, Backend>, json: String, targets: Vec<ConfigType>) -> Result<ImportPreview> {
+    backend.call("preview_import", json!({ "json": json, "targets": targets }))
+}
+
+#[tauri::command]
+pub fn import_config(backend: State<'_, Backend>, request: ImportRequest) -> Result<ImportReport> {
+    backend.call("import_config", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn export_config(backend: State<'_, Backend>, source: ConfigType, redact: Option<bool>) -> Result<String> {
+    backend.call("export_config", json!({ "source": source, "redact": redact }))
+}
 
-    Ok(())
+#[tauri::command]
+pub fn export_bundle(backend: State<'_, Backend>, request: ExportBundleRequest) -> Result<String> {
+    backend.call("export_bundle", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn preview_bundle(
+    backend: State<'_, Backend>,
+    json: String,
+    targets: Option<Vec<ConfigType>>,
+) -> Result<BundlePreview> {
+    backend.call("preview_bundle", json!({ "json": json, "targets": targets }))
+}
+
+#[tauri::command]
+pub fn import_bundle(backend: State<'_, Backend>, request: ImportBundleRequest) -> Result<BundleImportReport> {
+    backend.call("import_bundle", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn get_signing_identity(backend: State<'_, Backend>) -> Result<Option<SigningIdentity>> {
+    backend.call("get_signing_identity", json!({}))
+}
+
+#[tauri::command]
+pub fn generate_signing_key(backend: State<'_, Backend>, name: String, overwrite: Option<bool>) -> Result<SigningIdentity> {
+    backend.call("generate_signing_key", json!({ "name": name, "overwrite": overwrite }))
+}
+
+#[tauri::command]
+pub fn sign_bundle(backend: State<'_, Backend>, json: String) -> Result<String> {
+    backend.call("sign_bundle", json!({ "json": json }))
+}
+
+#[tauri::command]
+pub fn verify_bundle(backend: State<'_, Backend>, json: String) -> Result<Verification> {
+    backend.call("verify_bundle", json!({ "json": json }))
+}
+
+#[tauri::command]
+pub fn list_trusted_keys(backend: State<'_, Backend>) -> Result<Vec<TrustedKey>> {
+    backend.call("list_trusted_keys", json!({}))
+}
+
+#[tauri::command]
+pub fn trust_key(backend: State<'_, Backend>, name: String, public_key: String) -> Result<()> {
+    backend.call("trust_key", json!({ "name": name, "publicKey": public_key }))
+}
+
+#[tauri::command]
+pub fn remove_trusted_key(backend: State<'_, Backend>, public_key: String) -> Result<()> {
+    backend.call("remove_trusted_key", json!({ "publicKey": public_key }))
+}
+
+#[tauri::command]
+pub fn set_bundle_policy(backend: State<'_, Backend>, policy: BundlePolicy) -> Result<()> {
+    backend.call("set_bundle_policy", json!({ "policy": policy }))
+}
+
+#[tauri::command]
+pub fn start_server(backend: State<'_, Backend>, name: String, server: MCPServer) -> Result<RunningServer> {
+    backend.call("start_server", json!({ "name": name, "server": server }))
+}
+
+#[tauri::command]
+pub fn stop_server(backend: State<'_, Backend>, name: String) -> Result<()> {
+    backend.call("stop_server", json!({ "name": name }))
+}
+
+#[tauri::command]
+pub fn get_running_servers(backend: State<'_, Backend>) -> Result<Vec<RunningServer>> {
+    backend.call("get_running_servers", json!({}))
+}
+
+#[tauri::command]
+pub fn get_resource_samples(backend: State<'_, Backend>, name: String) -> Result<Vec<ResourceSample>> {
+    backend.call("get_resource_samples", json!({ "name": name }))
+}
+
+#[tauri::command]
+pub fn get_gateway_entry(backend: State<'_, Backend>) -> Result<MCPServer> {
+    backend.call("get_gateway_entry", json!({}))
+}
+
+#[tauri::command]
+pub fn install_gateway(backend: State<'_, Backend>, targets: Vec<ConfigType>) -> Result<()> {
+    backend.call("install_gateway", json!({ "targets": targets }))
+}
+
+#[tauri::command]
+pub fn enable_recording(backend: State<'_, Backend>, request: RecordingRequest) -> Result<()> {
+    backend.call("enable_recording", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn disable_recording(backend: State<'_, Backend>, request: RecordingRequest) -> Result<()> {
+    backend.call("disable_recording", json!({ "request": request }))
+}
+
+#[tauri::command]
+pub fn list_recordings(backend: State<'_, Backend>, server: Option<String>) -> Result<Vec<RecordingSession>> {
+    backend.call("list_recordings", json!({ "server": server }))
+}
+
+#[tauri::command]
+pub fn get_recording(
+    backend: State<'_, Backend>,
+    id: String,
+    filter: Option<MessageFilter>,
+) -> Result<Vec<RecordedMessage>> {
+    backend.call("get_recording", json!({ "id": id, "filter": filter }))
+}
+
+#[tauri::command]
+pub fn list_secrets(backend: State<'_, Backend>) -> Result<Vec<SecretInfo>> {
+    backend.call("list_secrets", json!({}))
+}
+
+#[tauri::command]
+pub fn set_secret(backend: State<'_, Backend>, name: String, value: String) -> Result<()> {
+    backend.call("set_secret", json!({ "name": name, "value": value }))
+}
+
+#[tauri::command]
+pub fn delete_secret(backend: State<'_, Backend>, name: String) -> Result<()> {
+    backend.call("delete_secret", json!({ "name": name }))
 }
 
 #[tauri::command]
-pub fn export_config(state: State<'_, ConfigManagerState>, source: ConfigType) -> Result<String> {
-    let manager = state.lock().map_err(|e| AppError::IoError(e.to_string()))?;
-    let config = manager.read_config(source)?;
-    let json = serde_json::to_string_pretty(&config.servers)?;
-    Ok(json)
+pub fn preview_redactions(backend: State<'_, Backend>, source: ConfigType) -> Result<Vec<Redaction>> {
+    backend.call("preview_redactions", json!({ "source": source }))
 }
@@ -1,4 +1,16 @@
+use crate::drift::{drift_fields, DriftStatus};
 use crate::error::{AppError, Result};
+use crate::interpolate::{canonical_copy, render_servers, validate_servers, InterpolationSyntax};
+use crate::metadata::ServerMetadata;
+use crate::plan::{self, ChangePlan, ConfigUpdate};
+use crate::profiles::{self, Profile};
+use crate::recorder;
+use crate::registry::Registry;
+use crate::secrets::{has_secret_refs, SecretVault};
+use crate::signing::BundlePolicy;
+use crate::stash::DisabledStash;
+use crate::sync::{self, keep_pinned, Agreement, Baseline, ChangeLog, SyncRule, SyncStrategy};
+use crate::templates::TemplateInstance;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -38,6 +50,30 @@ impl ConfigType {
         }
     }
 
+    /// Key of this client in `ManagedServer::systems`.
+    pub fn system_key(&self) -> &'static str {
+        match self {
+            ConfigType::ClaudeCode => "claudeCode",
+            ConfigType::ClaudeDesktop => "claudeDesktop",
+            ConfigType::RooCode => "rooCode",
+        }
+    }
+
+    /// Which `${...}` variables the client expands on its own.
+    pub fn interpolation(&self) -> InterpolationSyntax {
+        match self {
+            ConfigType::ClaudeCode => InterpolationSyntax::Shell,
+            ConfigType::ClaudeDesktop => InterpolationSyntax::Literal,
+            ConfigType::RooCode => InterpolationSyntax::EnvPrefixed,
+        }
+    }
+
+    /// Whether the client skips entries marked `"disabled": true` on its own.
+    /// Other clients' disabled servers are kept in mcphub's stash instead.
+    pub fn has_disabled_flag(&self) -> bool {
+        matches!(self, ConfigType::RooCode)
+    }
+
     pub fn config_path(&self) -> Result<PathBuf> {
         let home = dirs::home_dir().ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?;
 
@@ -63,23 +99,46 @@ impl ConfigType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MCPServer {
+    /// Empty for remote servers, which are reached through `url` instead
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub command: String,
     #[serde(default)]
     pub args: Vec<String>,
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub always_allow: Vec<String>,
 }
 
+impl MCPServer {
+    /// Copy of this server with `~` expanded in the command and args.
+    pub fn expanded(&self) -> MCPServer {
+        MCPServer {
+            command: ConfigManager::expand_path(&self.command),
+            args: self.args.iter().map(|a| ConfigManager::expand_path(a)).collect(),
+            ..self.clone()
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SystemStatus {
     pub enabled: bool,
     pub present: bool,
+    /// Whether the client's copy matches mcphub's canonical definition
+    #[serde(default)]
+    pub drift: DriftStatus,
+    /// Fields that differ, e.g. `args` or `env.GITHUB_TOKEN`; see `get_drift_report` for values
+    #[serde(default)]
+    pub drifted_fields: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -98,11 +157,29 @@ pub struct ManagedServer {
     pub command: String,
     pub args: Vec<String>,
     pub env: HashMap<String, String>,
+    pub url: Option<String>,
+    pub headers: HashMap<String, String>,
     pub always_allow: Vec<String>,
     pub systems: HashMap<String, SystemStatus>,
     pub health: HealthStatus,
     pub last_tested: Option<DateTime<Utc>>,
     pub error_message: Option<String>,
+    #[serde(default)]
+    pub metadata: ServerMetadata,
+}
+
+impl ManagedServer {
+    /// The server definition, without mcphub's per-system status.
+    pub fn to_server(&self) -> MCPServer {
+        MCPServer {
+            command: self.command.clone(),
+            args: self.args.clone(),
+            env: self.env.clone(),
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            always_allow: self.always_allow.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -113,6 +190,9 @@ pub struct MCPConfig {
     pub exists: bool,
     pub is_valid: bool,
     pub servers: HashMap<String, MCPServer>,
+    /// Servers disabled in this client, kept so they can be enabled again
+    #[serde(default)]
+    pub disabled: HashMap<String, MCPServer>,
     pub last_modified: Option<DateTime<Utc>>,
     pub error: Option<String>,
 }
@@ -123,6 +203,46 @@ pub struct AppState {
     pub servers: HashMap<String, ServerState>,
     pub last_sync: Option<DateTime<Utc>>,
     pub version: String,
+    /// How imports treat unsigned or untrusted bundles
+    #[serde(default)]
+    pub bundle_policy: BundlePolicy,
+    /// Fields each client keeps its own value for, by server name
+    #[serde(default)]
+    pub sync_rules: HashMap<String, SyncRule>,
+    /// Saved sets of enabled servers, by name
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+    /// Tags, group and notes, by server name
+    #[serde(default)]
+    pub metadata: HashMap<String, ServerMetadata>,
+    /// Directories of user and team templates, lowest priority first
+    #[serde(default)]
+    pub template_dirs: Vec<String>,
+    /// Which template each server was made from, by server name
+    #[serde(default)]
+    pub template_instances: HashMap<String, TemplateInstance>,
+    /// Servers the gateway serves from the registry after `install_gateway`
+    /// took them out of client configs
+    #[serde(default)]
+    pub gateway_servers: Vec<String>,
+}
+
+impl AppState {
+    /// Fields of `name` that syncs leave alone in `config_type`.
+    pub fn pinned(&self, name: &str, config_type: ConfigType) -> &[String] {
+        self.sync_rules
+            .get(name)
+            .and_then(|rule| rule.pinned.get(&config_type))
+            .map_or(&[], |fields| fields.as_slice())
+    }
+
+    /// Fields of `name` pinned in any client.
+    pub fn all_pinned(&self, name: &str) -> Vec<String> {
+        self.sync_rules
+            .get(name)
+            .map(|rule| rule.pinned.values().flatten().cloned().collect())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -138,6 +258,12 @@ pub struct ConfigManager {
     state_path: PathBuf,
 }
 
+impl Default for ConfigManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ConfigManager {
     pub fn new() -> Self {
         let state_path = dirs::home_dir()
@@ -173,6 +299,81 @@ impl ConfigManager {
         &self.state
     }
 
+    pub fn set_bundle_policy(&mut self, policy: BundlePolicy) -> Result<()> {
+        self.state.bundle_policy = policy;
+        self.save_state()
+    }
+
+    pub fn save_profile(&mut self, name: &str, profile: Profile) -> Result<()> {
+        if name.trim().is_empty() {
+            return Err(AppError::InvalidArgument("Profile name can't be empty".to_string()));
+        }
+        self.state.profiles.insert(name.to_string(), profile);
+        self.save_state()
+    }
+
+    pub fn delete_profile(&mut self, name: &str) -> Result<()> {
+        if self.state.profiles.remove(name).is_none() {
+            return Err(AppError::InvalidArgument(format!("No profile named {}", name)));
+        }
+        self.save_state()
+    }
+
+    /// Switch to a saved profile in one plan, returning the changes made.
+    pub fn apply_profile(&self, name: &str) -> Result<ChangePlan> {
+        let plan = profiles::plan(self, name)?;
+        plan::apply(self, &plan)?;
+        Ok(plan)
+    }
+
+    /// Replace a server's metadata; empty metadata removes it.
+    pub fn set_server_metadata(&mut self, name: &str, metadata: ServerMetadata) -> Result<()> {
+        let metadata = metadata.normalized();
+        if metadata.is_empty() {
+            self.state.metadata.remove(name);
+        } else {
+            self.state.metadata.insert(name.to_string(), metadata);
+        }
+        self.save_state()
+    }
+
+    pub fn record_template_instance(&mut self, name: &str, instance: TemplateInstance) -> Result<()> {
+        self.state.template_instances.insert(name.to_string(), instance);
+        self.save_state()
+    }
+
+    pub fn add_gateway_servers(&mut self, names: Vec<String>) -> Result<()> {
+        for name in names {
+            if !self.state.gateway_servers.contains(&name) {
+                self.state.gateway_servers.push(name);
+            }
+        }
+        self.state.gateway_servers.sort();
+        self.save_state()
+    }
+
+    pub fn set_template_dirs(&mut self, dirs: Vec<String>) -> Result<()> {
+        let mut kept: Vec<String> = Vec::new();
+        for dir in dirs.iter().map(|d| d.trim()).filter(|d| !d.is_empty()) {
+            if !kept.iter().any(|k| k == dir) {
+                kept.push(dir.to_string());
+            }
+        }
+        self.state.template_dirs = kept;
+        self.save_state()
+    }
+
+    /// Replace the sync rule for a server; an empty rule removes it.
+    pub fn set_sync_rule(&mut self, name: &str, rule: SyncRule) -> Result<()> {
+        rule.validate()?;
+        if rule.is_empty() {
+            self.state.sync_rules.remove(name);
+        } else {
+            self.state.sync_rules.insert(name.to_string(), rule);
+        }
+        self.save_state()
+    }
+
     pub fn update_server_health(
         &mut self,
         name: &str,
@@ -203,6 +404,7 @@ impl ConfigManager {
                 exists: false,
                 is_valid: false,
                 servers: HashMap::new(),
+                disabled: self.stashed_servers(config_type, &HashMap::new())?,
                 last_modified: None,
                 error: Some("Config file does not exist".to_string()),
             });
@@ -215,11 +417,21 @@ impl ConfigManager {
             .map(|t| DateTime::<Utc>::from(t));
 
         // Parse the config - different formats for different tools
-        let servers = match config_type {
+        let mut disabled_names: Vec<String> = Vec::new();
+        let servers: HashMap<String, MCPServer> = match config_type {
             ConfigType::ClaudeCode | ConfigType::RooCode => {
                 // Format: { "mcpServers": { ... } }
                 let parsed: serde_json::Value = serde_json::from_str(&content)?;
                 if let Some(mcp_servers) = parsed.get("mcpServers") {
+                    if config_type.has_disabled_flag() {
+                        disabled_names = mcp_servers
+                            .as_object()
+                            .into_iter()
+                            .flatten()
+                            .filter(|(_, entry)| entry.get("disabled") == Some(&serde_json::Value::Bool(true)))
+                            .map(|(name, _)| name.clone())
+                            .collect();
+                    }
                     serde_json::from_value(mcp_servers.clone()).unwrap_or_default()
                 } else {
                     HashMap::new()
@@ -236,20 +448,109 @@ impl ConfigManager {
             }
         };
 
+        // Variables are kept in mcphub's canonical syntax regardless of client
+        // dialect, including those the client could only be given as literals
+        let registry = Registry::open()?;
+        let baseline = Baseline::open()?;
+        let (disabled, servers): (HashMap<_, _>, HashMap<_, _>) = servers
+            .into_iter()
+            .map(|(name, server)| {
+                let known: Vec<&MCPServer> = registry.get(&name).into_iter().chain(baseline.get(&name)).collect();
+                let server = canonical_copy(&server, &known, config_type);
+                (name, server)
+            })
+            .partition(|(name, _)| disabled_names.contains(name));
+        let disabled = if config_type.has_disabled_flag() {
+            disabled
+        } else {
+            self.stashed_servers(config_type, &servers)?
+        };
+
         Ok(MCPConfig {
             config_type,
             path: path_str,
             exists: true,
             is_valid: true,
             servers,
+            disabled,
             last_modified,
             error: None,
         })
     }
 
+    /// Stashed servers of a client that has no disabled flag, except any it has enabled again.
+    fn stashed_servers(
+        &self,
+        config_type: ConfigType,
+        enabled: &HashMap<String, MCPServer>,
+    ) -> Result<HashMap<String, MCPServer>> {
+        if config_type.has_disabled_flag() {
+            return Ok(HashMap::new());
+        }
+        let mut stashed = DisabledStash::open()?.servers(config_type);
+        stashed.retain(|name, _| !enabled.contains_key(name));
+        Ok(stashed)
+    }
+
+    /// Write a client's enabled servers, keeping its disabled ones unless a
+    /// server of the same name is now enabled.
     pub fn write_config(&self, config_type: ConfigType, servers: &HashMap<String, MCPServer>) -> Result<()> {
+        let mut disabled = self.read_config(config_type)?.disabled;
+        disabled.retain(|name, _| !servers.contains_key(name));
+        self.write_servers(config_type, servers, &disabled)
+    }
+
+    /// Save what reads only work out in memory, before a write changes it:
+    /// servers mcphub hasn't seen yet are registered as they are in the
+    /// clients, and edits made outside mcphub are stamped with the file's time.
+    fn record_before_write(&self) -> Result<()> {
+        let configs = ConfigType::all()
+            .into_iter()
+            .map(|ct| self.read_config(ct))
+            .collect::<Result<Vec<_>>>()?;
+        let mut registry = Registry::open()?;
+        if registry.adopt(&configs) {
+            registry.save()?;
+        }
+
+        let mut log = ChangeLog::open()?;
+        let mut changed = false;
+        for config in configs.iter().filter(|c| c.exists) {
+            changed |= log.observe(config.config_type, &config.servers, config.last_modified.unwrap_or_else(Utc::now));
+        }
+        if changed {
+            log.save()?;
+        }
+        Ok(())
+    }
+
+    /// Write a client's enabled and disabled servers.
+    pub fn write_servers(
+        &self,
+        config_type: ConfigType,
+        servers: &HashMap<String, MCPServer>,
+        disabled: &HashMap<String, MCPServer>,
+    ) -> Result<()> {
+        self.record_before_write()?;
         let path = config_type.config_path()?;
 
+        // Clients need literal values, so secret references are resolved on the way out
+        let servers = &Self::resolve_secrets(servers)?;
+        // ...and variables rendered in the client's own dialect, or as literals
+        let servers = &render_servers(servers, config_type)?;
+
+        let mut entries = serde_json::to_value(servers)?;
+        if config_type.has_disabled_flag() {
+            let disabled = render_servers(&Self::resolve_secrets(disabled)?, config_type)?;
+            for (name, server) in disabled {
+                let mut entry = serde_json::to_value(server)?;
+                entry["disabled"] = serde_json::Value::Bool(true);
+                entries[name] = entry;
+            }
+        } else {
+            DisabledStash::open()?.set_servers(config_type, Self::reference_secrets(disabled))?;
+        }
+
         // Create directory if it doesn't exist
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
@@ -264,15 +565,52 @@ impl ConfigManager {
         };
 
         // Update mcpServers field
-        config_value["mcpServers"] = serde_json::to_value(servers)?;
+        config_value["mcpServers"] = entries;
 
         // Write back
         let json = serde_json::to_string_pretty(&config_value)?;
         fs::write(&path, json)?;
 
+        // Remember which servers this write changed, for prefer-newest syncs
+        let mut log = ChangeLog::open()?;
+        if log.observe(config_type, &self.read_config(config_type)?.servers, Utc::now()) {
+            log.save()?;
+        }
         Ok(())
     }
 
+    fn resolve_secrets(servers: &HashMap<String, MCPServer>) -> Result<HashMap<String, MCPServer>> {
+        if !servers.values().any(|s| has_secret_refs(&s.env)) {
+            return Ok(servers.clone());
+        }
+
+        let mut vault = SecretVault::open()?;
+        servers
+            .iter()
+            .map(|(name, server)| {
+                let mut server = server.clone();
+                server.env = vault.resolve_env(name, &server.env)?;
+                Ok((name.clone(), server))
+            })
+            .collect()
+    }
+
+    /// Swap literal env values that came from the vault back to secret references.
+    pub fn reference_secrets(servers: &HashMap<String, MCPServer>) -> HashMap<String, MCPServer> {
+        let Ok(vault) = SecretVault::open() else {
+            return servers.clone();
+        };
+
+        servers
+            .iter()
+            .map(|(name, server)| {
+                let mut server = server.clone();
+                server.env = vault.reference_env(name, &server.env);
+                (name.clone(), server)
+            })
+            .collect()
+    }
+
     pub fn backup_config(&self, config_type: ConfigType) -> Result<String> {
         let path = config_type.config_path()?;
 
@@ -297,45 +635,80 @@ impl ConfigManager {
     }
 
     pub fn get_managed_servers(&self) -> Result<Vec<ManagedServer>> {
+        // Recorded servers are listed as what they launch
         let configs: Vec<MCPConfig> = ConfigType::all()
             .into_iter()
-            .map(|ct| self.read_config(ct))
+            .map(|ct| self.read_config(ct).map(recorder::unproxied_config))
             .collect::<Result<Vec<_>>>()?;
 
+        // Servers first seen in a client config count as canonical as they are
+        // there; the next write saves them
+        let mut registry = Registry::open()?;
+        registry.adopt(&configs);
+
         // Collect all unique server names
         let mut all_servers: HashMap<String, ManagedServer> = HashMap::new();
-
-        for config in &configs {
-            for (name, server) in &config.servers {
-                let entry = all_servers.entry(name.clone()).or_insert_with(|| {
-                    let server_state = self.state.servers.get(name);
-                    ManagedServer {
-                        name: name.clone(),
-                        command: server.command.clone(),
-                        args: server.args.clone(),
-                        env: server.env.clone(),
-                        always_allow: server.always_allow.clone(),
-                        systems: HashMap::new(),
-                        health: server_state.map(|s| s.health).unwrap_or(HealthStatus::Untested),
-                        last_tested: server_state.and_then(|s| s.last_tested),
-                        error_message: server_state.and_then(|s| s.error_message.clone()),
-                    }
-                });
-
-                // Update system status
-                let system_key = match config.config_type {
-                    ConfigType::ClaudeCode => "claudeCode",
-                    ConfigType::ClaudeDesktop => "claudeDesktop",
-                    ConfigType::RooCode => "rooCode",
-                };
-
-                entry.systems.insert(
-                    system_key.to_string(),
-                    SystemStatus {
-                        enabled: true,
-                        present: true,
-                    },
-                );
+        let vault = SecretVault::open().ok();
+
+        for enabled in [true, false] {
+            for config in &configs {
+                let servers = if enabled { &config.servers } else { &config.disabled };
+                for (name, copy) in servers {
+                    let canonical = registry.get(name);
+                    let entry = all_servers.entry(name.clone()).or_insert_with(|| {
+                        let server = canonical.unwrap_or(copy);
+                        let server_state = self.state.servers.get(name);
+                        ManagedServer {
+                            name: name.clone(),
+                            command: server.command.clone(),
+                            args: server.args.clone(),
+                            env: match vault {
+                                Some(ref vault) => vault.reference_env(name, &server.env),
+                                None => server.env.clone(),
+                            },
+                            url: server.url.clone(),
+                            headers: server.headers.clone(),
+                            always_allow: server.always_allow.clone(),
+                            systems: HashMap::new(),
+                            health: server_state.map(|s| s.health).unwrap_or(HealthStatus::Untested),
+                            last_tested: server_state.and_then(|s| s.last_tested),
+                            error_message: server_state.and_then(|s| s.error_message.clone()),
+                            metadata: self.state.metadata.get(name).cloned().unwrap_or_default(),
+                        }
+                    });
+
+                    // Update system status
+                    let system_key = config.config_type.system_key();
+                    let referenced = MCPServer {
+                        env: match vault {
+                            Some(ref vault) => vault.reference_env(name, &copy.env),
+                            None => copy.env.clone(),
+                        },
+                        ..copy.clone()
+                    };
+                    let pinned = self.state.pinned(name, config.config_type);
+                    let mut drifted_fields: Vec<String> = canonical
+                        .map(|c| drift_fields(c, &referenced, pinned))
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|change| change.field)
+                        .collect();
+                    drifted_fields.dedup();
+
+                    entry.systems.insert(
+                        system_key.to_string(),
+                        SystemStatus {
+                            enabled,
+                            present: true,
+                            drift: if drifted_fields.is_empty() {
+                                DriftStatus::InSync
+                            } else {
+                                DriftStatus::Drifted
+                            },
+                            drifted_fields,
+                        },
+                    );
+                }
             }
         }
 
@@ -345,8 +718,15 @@ impl ConfigManager {
                 server.systems.entry(system_key.to_string()).or_insert(SystemStatus {
                     enabled: false,
                     present: false,
+                    drift: DriftStatus::InSync,
+                    drifted_fields: Vec::new(),
                 });
             }
+
+            // Kept only as disabled copies, so it isn't running anywhere
+            if !server.systems.values().any(|s| s.enabled) {
+                server.health = HealthStatus::Disabled;
+            }
         }
 
         let mut servers: Vec<ManagedServer> = all_servers.into_values().collect();
@@ -370,32 +750,119 @@ impl ConfigManager {
         server: &MCPServer,
         targets: &[ConfigType],
     ) -> Result<()> {
+        let server = server.expanded();
+        validate_servers(&HashMap::from([(name.to_string(), server.clone())]), targets)?;
+
         for config_type in targets {
             let mut config = self.read_config(*config_type)?;
+            config.servers.insert(name.to_string(), server.clone());
+            self.write_config(*config_type, &config.servers)?;
+        }
+
+        Registry::open()?.set(&HashMap::from([(name.to_string(), server)]))
+    }
 
-            // Expand paths in args
-            let expanded_server = MCPServer {
-                command: Self::expand_path(&server.command),
-                args: server.args.iter().map(|a| Self::expand_path(a)).collect(),
-                env: server.env.clone(),
-                always_allow: server.always_allow.clone(),
-            };
+    /// Rename a server in place in every client that has it, enabled or
+    /// disabled, and carry its mcphub state over: health, metadata, template
+    /// record, sync rules, profiles, canonical definition, baseline, change
+    /// times, secret bindings and recordings.
+    pub fn rename_server(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        if new_name.trim().is_empty() {
+            return Err(AppError::InvalidArgument("Server name can't be empty".to_string()));
+        }
+        if new_name == old_name {
+            return Ok(());
+        }
 
-            config.servers.insert(name.to_string(), expanded_server);
-            self.write_config(*config_type, &config.servers)?;
+        let configs: Vec<MCPConfig> = ConfigType::all()
+            .into_iter()
+            .map(|ct| self.read_config(ct))
+            .collect::<Result<Vec<_>>>()?;
+        let has = |config: &MCPConfig, name: &str| config.servers.contains_key(name) || config.disabled.contains_key(name);
+        if configs.iter().any(|c| has(c, new_name)) || Registry::open()?.get(new_name).is_some() {
+            return Err(AppError::InvalidArgument(format!("A server named {} already exists", new_name)));
+        }
+        if !configs.iter().any(|c| has(c, old_name)) {
+            return Err(AppError::ServerNotFound(old_name.to_string()));
         }
 
-        Ok(())
+        let mut updates = Vec::new();
+        for before in configs.into_iter().filter(|c| has(c, old_name)) {
+            let (mut servers, mut disabled) = (before.servers.clone(), before.disabled.clone());
+            for map in [&mut servers, &mut disabled] {
+                if let Some(mut server) = map.remove(old_name) {
+                    recorder::rename_proxied(&mut server, new_name);
+                    map.insert(new_name.to_string(), server);
+                }
+            }
+            updates.push(ConfigUpdate {
+                before,
+                servers,
+                disabled,
+            });
+        }
+
+        self.record_before_write()?;
+        let before = ChangeLog::open()?;
+        plan::write_updates(self, updates)?;
+
+        Registry::open()?.rename(old_name, new_name)?;
+        Baseline::open()?.rename(old_name, new_name)?;
+        ChangeLog::open()?.rename(&before, old_name, new_name)?;
+        SecretVault::open()?.rename_server(old_name, new_name)?;
+        recorder::rename_sessions(old_name, new_name)?;
+
+        let state = &mut self.state;
+        if let Some(server_state) = state.servers.remove(old_name) {
+            state.servers.insert(new_name.to_string(), server_state);
+        }
+        if let Some(rule) = state.sync_rules.remove(old_name) {
+            state.sync_rules.insert(new_name.to_string(), rule);
+        }
+        if let Some(metadata) = state.metadata.remove(old_name) {
+            state.metadata.insert(new_name.to_string(), metadata);
+        }
+        if let Some(instance) = state.template_instances.remove(old_name) {
+            state.template_instances.insert(new_name.to_string(), instance);
+        }
+        for served in state.gateway_servers.iter_mut().filter(|s| *s == old_name) {
+            *served = new_name.to_string();
+        }
+        for profile in state.profiles.values_mut() {
+            if let Some(clients) = profile.enabled.remove(old_name) {
+                profile.enabled.insert(new_name.to_string(), clients);
+            }
+        }
+        self.save_state()
     }
 
-    pub fn remove_server(&self, name: &str, targets: &[ConfigType]) -> Result<()> {
+    pub fn remove_server(&mut self, name: &str, targets: &[ConfigType]) -> Result<()> {
         for config_type in targets {
             let mut config = self.read_config(*config_type)?;
             config.servers.remove(name);
-            self.write_config(*config_type, &config.servers)?;
+            config.disabled.remove(name);
+            self.write_servers(*config_type, &config.servers, &config.disabled)?;
         }
 
-        Ok(())
+        // Only forget the definition once no client has it any more
+        for config_type in ConfigType::all() {
+            let config = self.read_config(config_type)?;
+            if config.servers.contains_key(name) || config.disabled.contains_key(name) {
+                return Ok(());
+            }
+        }
+        Registry::open()?.remove(name)?;
+
+        // ...along with everything kept about it by name
+        self.state.servers.remove(name);
+        self.state.sync_rules.remove(name);
+        self.state.metadata.remove(name);
+        self.state.template_instances.remove(name);
+        self.state.gateway_servers.retain(|served| served != name);
+        for profile in self.state.profiles.values_mut() {
+            profile.enabled.remove(name);
+        }
+        self.save_state()
     }
 
     pub fn set_server_enabled(
@@ -408,53 +875,75 @@ impl ConfigManager {
         let mut config = self.read_config(config_type)?;
 
         if enabled {
-            // Add server if we have a template
-            if let Some(template) = server_template {
-                let expanded_server = MCPServer {
-                    command: Self::expand_path(&template.command),
-                    args: template.args.iter().map(|a| Self::expand_path(a)).collect(),
-                    env: template.env.clone(),
-                    always_allow: template.always_allow.clone(),
-                };
-                config.servers.insert(name.to_string(), expanded_server);
+            // Restore the client's own definition if it has one, else use the template
+            let restored = config.disabled.remove(name);
+            if let Some(server) = restored.or_else(|| server_template.map(MCPServer::expanded)) {
+                config.servers.insert(name.to_string(), server);
             }
-        } else {
-            // Remove server
-            config.servers.remove(name);
+        } else if let Some(server) = config.servers.remove(name) {
+            // Keep the definition so enabling brings it back as it was
+            config.disabled.insert(name.to_string(), server);
         }
 
-        self.write_config(config_type, &config.servers)?;
-        Ok(())
+        self.write_servers(config_type, &config.servers, &config.disabled)
     }
 
-    pub fn sync_server(&self, name: &str, source: ConfigType, targets: &[ConfigType]) -> Result<()> {
-        let source_config = self.read_config(source)?;
+    pub fn sync_server(&mut self, name: &str, source: ConfigType, targets: &[ConfigType]) -> Result<()> {
+        let source_config = recorder::unproxied_config(self.read_config(source)?);
 
         let server = source_config
             .servers
             .get(name)
             .ok_or_else(|| AppError::ServerNotFound(name.to_string()))?;
+        let others: Vec<ConfigType> = targets.iter().copied().filter(|t| *t != source).collect();
+        validate_servers(&HashMap::from([(name.to_string(), server.clone())]), &others)?;
+
+        // The source's copy becomes canonical, apart from its own pinned fields
+        let mut registry = Registry::open()?;
+        let canonical = keep_pinned(server, registry.get(name), self.state.pinned(name, source));
+        registry.set(&HashMap::from([(name.to_string(), canonical)]))?;
 
         for target in targets {
             if *target != source {
                 let mut target_config = self.read_config(*target)?;
-                target_config.servers.insert(name.to_string(), server.clone());
+                // Syncing doesn't enable a server that was disabled on purpose
+                if target_config.disabled.contains_key(name) {
+                    continue;
+                }
+                let mut pinned = self.state.pinned(name, *target).to_vec();
+                pinned.extend_from_slice(self.state.pinned(name, source));
+                let current = target_config.servers.get(name);
+                let server = keep_pinned(server, current.map(recorder::unproxied).as_ref(), &pinned);
+                let server = recorder::proxied_like(current, server);
+                target_config.servers.insert(name.to_string(), server);
                 self.write_config(*target, &target_config.servers)?;
             }
         }
 
-        Ok(())
+        self.mark_synced()
     }
 
-    pub fn sync_all(&self, source: ConfigType) -> Result<()> {
-        let source_config = self.read_config(source)?;
+    /// Reconcile every client from `source`, returning the changes made.
+    pub fn sync_all(
+        &mut self,
+        source: ConfigType,
+        strategy: SyncStrategy,
+        resolutions: &HashMap<String, ConfigType>,
+    ) -> Result<ChangePlan> {
+        let plan = sync::plan(self, source, strategy, resolutions)?;
+        plan::apply(self, &plan)?;
+        self.mark_synced()?;
+        Ok(plan)
+    }
 
-        for target in ConfigType::all() {
-            if target != source {
-                self.write_config(target, &source_config.servers)?;
-            }
+    /// Snapshot what the clients now agree on, as the base for three-way merges
+    /// and as the canonical definitions.
+    fn mark_synced(&mut self) -> Result<()> {
+        if let Some(agreement) = Agreement::of(self)? {
+            Baseline::open()?.record(&agreement)?;
+            Registry::open()?.record(&agreement, &self.state)?;
         }
-
-        Ok(())
+        self.state.last_sync = Some(Utc::now());
+        self.save_state()
     }
 }
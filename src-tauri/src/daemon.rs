@@ -0,0 +1,318 @@
+use crate::error::{AppError, Result};
+use crate::hub::{self, Hub, ServiceOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::sync::{mpsc, Arc, Mutex};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+/// Method that turns a connection into a stream of event notifications
+const SUBSCRIBE: &str = "subscribe";
+
+/// Control socket, only reachable by the current user. It lives in a
+/// directory only they can enter, so there's no moment between binding and
+/// restricting the socket itself when others could connect.
+pub fn socket_path() -> Result<PathBuf> {
+    let dir = dirs::home_dir()
+        .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+        .join(".mcphub")
+        .join("run");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        std::fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+        // Created before, or by something with a looser umask
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    #[cfg(not(unix))]
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir.join("mcphub.sock"))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Missing for notifications, which get no reply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    /// The `AppError` itself, so clients surface the same error as in-process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<AppError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl Response {
+    fn result(id: Value, result: Value) -> Self {
+        Response {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn error(id: Value, code: i64, message: String, data: Option<AppError>) -> Self {
+        Response {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(RpcError { code, message, data }),
+        }
+    }
+
+    fn from_app_error(id: Value, error: AppError) -> Self {
+        let code = match error {
+            AppError::UnknownMethod(_) => METHOD_NOT_FOUND,
+            AppError::InvalidArgument(_) => INVALID_PARAMS,
+            _ => SERVER_ERROR,
+        };
+        Response::error(id, code, error.to_string(), Some(error))
+    }
+}
+
+fn notification(event: &str, payload: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": event, "params": payload })
+}
+
+/// Connections that asked for events.
+#[cfg(unix)]
+#[derive(Default)]
+struct Subscribers(Mutex<Vec<mpsc::Sender<String>>>);
+
+#[cfg(unix)]
+impl Subscribers {
+    fn add(&self) -> mpsc::Receiver<String> {
+        let (tx, rx) = mpsc::channel();
+        if let Ok(mut senders) = self.0.lock() {
+            senders.push(tx);
+        }
+        rx
+    }
+
+    /// Send an event to every subscriber, dropping those that have gone away.
+    fn publish(&self, event: &str, payload: Value) {
+        let line = notification(event, payload).to_string();
+        if let Ok(mut senders) = self.0.lock() {
+            senders.retain(|tx| tx.send(line.clone()).is_ok());
+        }
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<ServiceOptions> {
+    let mut health_interval = Some(hub::HEALTH_CHECK_INTERVAL);
+    if let Some(i) = args.iter().position(|a| a == "--health-interval") {
+        let minutes: u64 = args
+            .get(i + 1)
+            .and_then(|m| m.parse().ok())
+            .ok_or_else(|| AppError::InvalidArgument("--health-interval takes a number of minutes".to_string()))?;
+        // Zero turns scheduled checks off
+        health_interval = (minutes > 0).then(|| Duration::from_secs(minutes * 60));
+    }
+    Ok(ServiceOptions {
+        watch: true,
+        health_interval,
+    })
+}
+
+/// Run headless: keep the watcher, health checks and supervisor going and
+/// serve the hub's operations on the control socket until killed.
+#[cfg(unix)]
+pub fn run(args: &[String]) -> Result<()> {
+    let options = parse_options(args)?;
+    let path = socket_path()?;
+
+    if UnixStream::connect(&path).is_ok() {
+        return Err(AppError::DaemonError(format!("Already running on {}", path.display())));
+    }
+    // Left behind by a daemon that didn't shut down cleanly
+    std::fs::remove_file(&path).ok();
+
+    let listener = UnixListener::bind(&path)?;
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    let hub = Arc::new(Hub::new());
+    let subscribers = Arc::new(Subscribers::default());
+    let sink = Arc::clone(&subscribers);
+    hub.spawn_services(options, Arc::new(move |event, payload| sink.publish(event, payload)));
+
+    eprintln!("mcphub daemon listening on {}", path.display());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        let hub = Arc::clone(&hub);
+        let subscribers = Arc::clone(&subscribers);
+        std::thread::spawn(move || {
+            serve(&hub, &subscribers, stream).ok();
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn run(_args: &[String]) -> Result<()> {
+    Err(AppError::DaemonError("The daemon needs Unix domain sockets".to_string()))
+}
+
+/// Answer requests on one connection, one JSON-RPC message per line.
+#[cfg(unix)]
+fn serve(hub: &Hub, subscribers: &Subscribers, stream: UnixStream) -> Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let response = Response::error(Value::Null, PARSE_ERROR, e.to_string(), None);
+                writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+                continue;
+            }
+        };
+
+        if request.method == SUBSCRIBE {
+            let events = subscribers.add();
+            let response = Response::result(request.id.unwrap_or(Value::Null), json!(true));
+            writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+            for event in events {
+                writeln!(writer, "{}", event)?;
+            }
+            return Ok(());
+        }
+
+        let result = hub.call(&request.method, &request.params);
+        let Some(id) = request.id else {
+            continue;
+        };
+        let response = match result {
+            Ok(value) => Response::result(id, value),
+            Err(e) => Response::from_app_error(id, e),
+        };
+        writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+    }
+    Ok(())
+}
+
+/// Talks to a running daemon, one connection per call.
+#[derive(Debug, Clone)]
+pub struct Client {
+    path: PathBuf,
+}
+
+impl Client {
+    /// A client for the running daemon, or `None` if none is listening.
+    #[cfg(unix)]
+    pub fn connect() -> Option<Self> {
+        let path = socket_path().ok()?;
+        UnixStream::connect(&path).ok()?;
+        Some(Client { path })
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect() -> Option<Self> {
+        None
+    }
+
+    /// Whether the daemon still accepts connections.
+    #[cfg(unix)]
+    pub fn is_listening(&self) -> bool {
+        UnixStream::connect(&self.path).is_ok()
+    }
+
+    #[cfg(not(unix))]
+    pub fn is_listening(&self) -> bool {
+        false
+    }
+
+    #[cfg(unix)]
+    fn send(&self, method: &str, params: Value) -> Result<(BufReader<UnixStream>, Response)> {
+        let mut stream = UnixStream::connect(&self.path)
+            .map_err(|e| AppError::DaemonError(format!("Could not reach the daemon: {}", e)))?;
+        let request = Request {
+            jsonrpc: "2.0".to_string(),
+            id: Some(json!(1)),
+            method: method.to_string(),
+            params,
+        };
+        writeln!(stream, "{}", serde_json::to_string(&request)?)?;
+
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if line.is_empty() {
+            return Err(AppError::DaemonError("The daemon closed the connection".to_string()));
+        }
+        Ok((reader, serde_json::from_str(&line)?))
+    }
+
+    #[cfg(unix)]
+    pub fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let (_, response) = self.send(method, params)?;
+        match response.error {
+            Some(error) => Err(error.data.unwrap_or(AppError::DaemonError(error.message))),
+            // A `null` result deserializes as missing
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn call(&self, _method: &str, _params: Value) -> Result<Value> {
+        Err(AppError::DaemonError("The daemon needs Unix domain sockets".to_string()))
+    }
+
+    /// Pass each event to `on_event` until the daemon goes away.
+    #[cfg(unix)]
+    pub fn subscribe(&self, mut on_event: impl FnMut(&str, Value)) -> Result<()> {
+        let (reader, _) = self.send(SUBSCRIBE, Value::Null)?;
+        for line in reader.lines() {
+            let Ok(message) = serde_json::from_str::<Request>(&line?) else {
+                continue;
+            };
+            on_event(&message.method, message.params);
+        }
+        Err(AppError::DaemonError("The daemon stopped".to_string()))
+    }
+
+    #[cfg(not(unix))]
+    pub fn subscribe(&self, _on_event: impl FnMut(&str, Value)) -> Result<()> {
+        Err(AppError::DaemonError("The daemon needs Unix domain sockets".to_string()))
+    }
+}
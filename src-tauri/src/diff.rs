@@ -0,0 +1,198 @@
+use crate::config::{ConfigManager, ConfigType, MCPServer};
+use crate::error::Result;
+use crate::recorder;
+use crate::redact::PLACEHOLDER_SCHEME;
+use crate::secrets::SECRET_SCHEME;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// Shown in place of env and header values that might be secrets
+const MASK: &str = "********";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffStatus {
+    OnlyInA,
+    OnlyInB,
+    Identical,
+    Differing,
+}
+
+/// Direction of a change, reading from A to B.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    /// `transport`, `command`, `args`, `url`, `env.KEY`, `headers.KEY` or `alwaysAllow`
+    pub field: String,
+    pub kind: ChangeKind,
+    pub a: Option<Value>,
+    pub b: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerDiff {
+    pub name: String,
+    pub status: DiffStatus,
+    /// Empty unless the status is `differing`
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigDiff {
+    pub a: ConfigType,
+    pub b: ConfigType,
+    /// Every server in either config, by name
+    pub servers: Vec<ServerDiff>,
+}
+
+impl ConfigDiff {
+    pub fn with_status(&self, status: DiffStatus) -> impl Iterator<Item = &ServerDiff> {
+        self.servers.iter().filter(move |s| s.status == status)
+    }
+}
+
+/// Stdio servers are launched from a command; remote ones are reached by URL.
+fn transport(server: &MCPServer) -> &'static str {
+    if server.url.is_some() {
+        "http"
+    } else {
+        "stdio"
+    }
+}
+
+/// Rewrite forms that mean the same thing to one spelling, e.g. `~/bin`
+/// and `/home/me/bin`, since `add_server` expands `~` but hand edits may not.
+pub fn normalize(server: &MCPServer) -> MCPServer {
+    let mut server = server.expanded();
+    for value in server.env.values_mut() {
+        *value = ConfigManager::expand_path(value);
+    }
+    server
+}
+
+/// Hide values unless they only refer to a secret or variable stored elsewhere.
+pub fn masked(value: &str) -> &str {
+    if value.starts_with(SECRET_SCHEME) || value.starts_with(PLACEHOLDER_SCHEME) || value.contains("${") {
+        value
+    } else {
+        MASK
+    }
+}
+
+fn mask(value: &str) -> Value {
+    json!(masked(value))
+}
+
+fn change(field: &str, a: Option<Value>, b: Option<Value>) -> FieldChange {
+    let kind = match (&a, &b) {
+        (None, _) => ChangeKind::Added,
+        (_, None) => ChangeKind::Removed,
+        _ => ChangeKind::Modified,
+    };
+    FieldChange {
+        field: field.to_string(),
+        kind,
+        a,
+        b,
+    }
+}
+
+/// Per-key changes between two maps of possibly secret values.
+fn diff_masked(prefix: &str, a: &HashMap<String, String>, b: &HashMap<String, String>) -> Vec<FieldChange> {
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    keys.into_iter()
+        .filter(|key| a.get(*key) != b.get(*key))
+        .map(|key| {
+            change(
+                &format!("{}.{}", prefix, key),
+                a.get(key).map(|v| mask(v)),
+                b.get(key).map(|v| mask(v)),
+            )
+        })
+        .collect()
+}
+
+/// Field-level changes from `a` to `b`, after normalizing both.
+pub fn diff_servers(a: &MCPServer, b: &MCPServer) -> Vec<FieldChange> {
+    let (a, b) = (normalize(a), normalize(b));
+    let mut changes = Vec::new();
+
+    if transport(&a) != transport(&b) {
+        changes.push(change("transport", Some(json!(transport(&a))), Some(json!(transport(&b)))));
+    }
+    if a.command != b.command {
+        let value = |c: &String| (!c.is_empty()).then(|| json!(c));
+        changes.push(change("command", value(&a.command), value(&b.command)));
+    }
+    if a.args != b.args {
+        changes.push(change("args", Some(json!(a.args)), Some(json!(b.args))));
+    }
+    if a.url != b.url {
+        changes.push(change("url", a.url.as_ref().map(|u| json!(u)), b.url.as_ref().map(|u| json!(u))));
+    }
+    changes.extend(diff_masked("env", &a.env, &b.env));
+    changes.extend(diff_masked("headers", &a.headers, &b.headers));
+
+    // Which tools are pre-approved matters, not the order they're listed in
+    let allowed_a: BTreeSet<&String> = a.always_allow.iter().collect();
+    let allowed_b: BTreeSet<&String> = b.always_allow.iter().collect();
+    for tool in allowed_a.difference(&allowed_b) {
+        changes.push(change("alwaysAllow", Some(json!(tool)), None));
+    }
+    for tool in allowed_b.difference(&allowed_a) {
+        changes.push(change("alwaysAllow", None, Some(json!(tool))));
+    }
+
+    changes
+}
+
+/// Classify every server in either map.
+pub fn diff_server_maps(a: &HashMap<String, MCPServer>, b: &HashMap<String, MCPServer>) -> Vec<ServerDiff> {
+    let names: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    names
+        .into_iter()
+        .map(|name| {
+            let (status, changes) = match (a.get(name), b.get(name)) {
+                (Some(_), None) => (DiffStatus::OnlyInA, Vec::new()),
+                (None, Some(_)) => (DiffStatus::OnlyInB, Vec::new()),
+                (Some(x), Some(y)) => {
+                    let changes = diff_servers(x, y);
+                    let status = if changes.is_empty() {
+                        DiffStatus::Identical
+                    } else {
+                        DiffStatus::Differing
+                    };
+                    (status, changes)
+                }
+                (None, None) => unreachable!("name comes from one of the maps"),
+            };
+            ServerDiff {
+                name: name.clone(),
+                status,
+                changes,
+            }
+        })
+        .collect()
+}
+
+/// Compare the servers of two client configs.
+pub fn diff_configs(manager: &ConfigManager, a: ConfigType, b: ConfigType) -> Result<ConfigDiff> {
+    let servers_a = recorder::unproxied_config(manager.read_config(a)?).servers;
+    let servers_b = recorder::unproxied_config(manager.read_config(b)?).servers;
+    Ok(ConfigDiff {
+        a,
+        b,
+        servers: diff_server_maps(&servers_a, &servers_b),
+    })
+}
@@ -0,0 +1,174 @@
+use crate::config::{ConfigManager, ConfigType, MCPConfig, MCPServer};
+use crate::diff::{self, FieldChange};
+use crate::error::Result;
+use crate::recorder;
+use crate::registry::Registry;
+use crate::sync::{keep_pinned, ChangeLog};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DriftStatus {
+    /// Matches the canonical definition, or the client doesn't have the server
+    #[default]
+    InSync,
+    /// Differs from the canonical definition beyond its pinned fields
+    Drifted,
+}
+
+/// Changes from the canonical definition to a client's copy, ignoring the
+/// fields pinned for that client. Both should hold secrets as vault references.
+pub fn drift_fields(canonical: &MCPServer, copy: &MCPServer, pinned: &[String]) -> Vec<FieldChange> {
+    let blank = MCPServer::default();
+    let strip = |server: &MCPServer| keep_pinned(server, Some(&blank), pinned);
+    diff::diff_servers(&strip(canonical), &strip(copy))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientDrift {
+    pub config_type: ConfigType,
+    pub enabled: bool,
+    pub status: DriftStatus,
+    /// From the canonical definition to this client's copy, masked like `diff_configs`
+    pub fields: Vec<FieldChange>,
+    pub changed_at: Option<DateTime<Utc>>,
+}
+
+/// A `sync_server` call that would settle the drift.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestedSync {
+    pub source: ConfigType,
+    /// Empty if the source is the only client with the server enabled; syncing
+    /// then just makes its copy canonical
+    pub targets: Vec<ConfigType>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftEntry {
+    pub server: String,
+    /// Every client that has the server, enabled or disabled
+    pub clients: Vec<ClientDrift>,
+    pub suggestion: Option<SuggestedSync>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftReport {
+    /// Only servers with at least one drifted client
+    pub servers: Vec<DriftEntry>,
+}
+
+fn names(config_types: &[ConfigType]) -> String {
+    config_types.iter().map(|ct| ct.display_name()).collect::<Vec<_>>().join(", ")
+}
+
+/// Pick the sync that settles a server's drift. The most recently changed
+/// enabled copy wins: if it's canonical, the drifted clients are restored from
+/// it; if it drifted, it's most likely a deliberate edit and is copied everywhere.
+fn suggest(clients: &[ClientDrift]) -> (Option<SuggestedSync>, String) {
+    let enabled: Vec<&ClientDrift> = clients.iter().filter(|c| c.enabled).collect();
+    // Ties go to a copy that's in sync
+    let Some(newest) = enabled
+        .iter()
+        .max_by_key(|c| (c.changed_at, c.status == DriftStatus::InSync))
+    else {
+        return (None, "Only disabled copies differ; enable the server to sync it".to_string());
+    };
+
+    let source = newest.config_type;
+    if newest.status == DriftStatus::InSync {
+        let targets: Vec<ConfigType> = enabled
+            .iter()
+            .filter(|c| c.status == DriftStatus::Drifted)
+            .map(|c| c.config_type)
+            .collect();
+        let reason = if targets.is_empty() {
+            "Only disabled copies differ; enable the server to sync it".to_string()
+        } else {
+            format!("Restore the canonical definition in {} from {}", names(&targets), source.display_name())
+        };
+        return ((!targets.is_empty()).then_some(SuggestedSync { source, targets }), reason);
+    }
+
+    let targets: Vec<ConfigType> = enabled
+        .iter()
+        .map(|c| c.config_type)
+        .filter(|ct| *ct != source)
+        .collect();
+    let reason = if targets.is_empty() {
+        format!("Make {}'s copy canonical", source.display_name())
+    } else {
+        format!("{} has the latest edit; copy it to {}", source.display_name(), names(&targets))
+    };
+    (Some(SuggestedSync { source, targets }), reason)
+}
+
+/// Every divergence from the canonical definitions, with a suggested sync for each.
+pub fn report(manager: &ConfigManager) -> Result<DriftReport> {
+    let managed = manager.get_managed_servers()?;
+
+    let mut configs: Vec<MCPConfig> = ConfigType::all()
+        .into_iter()
+        .map(|ct| manager.read_config(ct).map(recorder::unproxied_config))
+        .collect::<Result<Vec<_>>>()?;
+    configs.retain(|c| c.exists);
+    // Servers mcphub hasn't seen yet count as canonical as they are
+    let mut registry = Registry::open()?;
+    registry.adopt(&configs);
+
+    let mut log = ChangeLog::open()?;
+    for config in configs.iter_mut() {
+        log.observe(config.config_type, &config.servers, config.last_modified.unwrap_or_else(Utc::now));
+        // Compared with the registry, which keeps secrets as references
+        config.servers = ConfigManager::reference_secrets(&config.servers);
+        config.disabled = ConfigManager::reference_secrets(&config.disabled);
+    }
+
+    let mut servers = Vec::new();
+    for server in managed {
+        let Some(canonical) = registry.get(&server.name) else {
+            continue;
+        };
+
+        let mut clients = Vec::new();
+        for config in &configs {
+            let (copy, is_enabled) = match (config.servers.get(&server.name), config.disabled.get(&server.name)) {
+                (Some(copy), _) => (copy, true),
+                (None, Some(copy)) => (copy, false),
+                (None, None) => continue,
+            };
+
+            let pinned = manager.get_state().pinned(&server.name, config.config_type);
+            let fields = drift_fields(canonical, copy, pinned);
+            clients.push(ClientDrift {
+                config_type: config.config_type,
+                enabled: is_enabled,
+                status: if fields.is_empty() {
+                    DriftStatus::InSync
+                } else {
+                    DriftStatus::Drifted
+                },
+                fields,
+                changed_at: log.changed_at(&server.name, config.config_type).or(config.last_modified),
+            });
+        }
+
+        if clients.iter().all(|c| c.status == DriftStatus::InSync) {
+            continue;
+        }
+        let (suggestion, reason) = suggest(&clients);
+        servers.push(DriftEntry {
+            server: server.name,
+            clients,
+            suggestion,
+            reason,
+        });
+    }
+
+    Ok(DriftReport { servers })
+}
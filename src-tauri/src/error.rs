@@ -1,7 +1,7 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Error, Debug, Serialize)]
+#[derive(Error, Debug, Serialize, Deserialize)]
 pub enum AppError {
     #[error("Failed to read config file: {0}")]
     ConfigReadError(String),
@@ -32,6 +32,36 @@ pub enum AppError {
 
     #[error("Path expansion error: {0}")]
     PathError(String),
+
+    #[error("Secret not found: {0}")]
+    SecretNotFound(String),
+
+    #[error("Secret vault error: {0}")]
+    SecretError(String),
+
+    #[error("Missing values for placeholders: {0}")]
+    MissingPlaceholders(String),
+
+    #[error("Unresolved variables: {0}")]
+    UnresolvedVariables(String),
+
+    #[error("Invalid bundle: {0}")]
+    InvalidBundle(String),
+
+    #[error("Untrusted bundle: {0}")]
+    UntrustedBundle(String),
+
+    #[error("Signing error: {0}")]
+    SigningError(String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("Unknown method: {0}")]
+    UnknownMethod(String),
+
+    #[error("Daemon error: {0}")]
+    DaemonError(String),
 }
 
 impl From<std::io::Error> for AppError {
@@ -0,0 +1,563 @@
+use crate::config::{ConfigManager, ConfigType, HealthStatus, MCPServer};
+use crate::error::{AppError, Result};
+use crate::interpolate;
+use crate::recorder;
+use crate::registry::Registry;
+use crate::supervisor::{resolve_command, server_command};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::process::{Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Name of the single entry the gateway occupies in client configs
+pub const GATEWAY_SERVER_NAME: &str = "mcphub";
+
+/// Separator between server and tool name in namespaced tool names
+pub const TOOL_SEPARATOR: &str = "__";
+
+const PROTOCOL_VERSION: &str = "2025-03-26";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest HTTP request body the gateway reads
+const MAX_BODY: usize = 4 * 1024 * 1024;
+
+/// Environment variable holding the bearer token HTTP clients must send
+pub const TOKEN_VARIABLE: &str = "MCPHUB_GATEWAY_TOKEN";
+
+/// The client config entry that launches this executable as a stdio gateway.
+pub fn gateway_entry() -> Result<MCPServer> {
+    let exe = std::env::current_exe()?;
+    Ok(MCPServer {
+        command: exe.to_string_lossy().to_string(),
+        args: vec!["gateway".to_string()],
+        ..Default::default()
+    })
+}
+
+fn rpc_result(id: &Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn rpc_error(id: &Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// A running MCP server the gateway talks to over stdio.
+struct Upstream {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: u64,
+}
+
+impl Upstream {
+    fn spawn(server: &MCPServer) -> Result<Self> {
+        let expanded_command = ConfigManager::expand_path(&server.command);
+        let command_path = resolve_command(&expanded_command)
+            .ok_or_else(|| AppError::ProcessError(format!("Command not found: {}", expanded_command)))?;
+        let args: Vec<String> = server.args.iter().map(|a| ConfigManager::expand_path(a)).collect();
+
+        let mut cmd = server_command(&command_path, &args, &server.env)?;
+        cmd.stdin(Stdio::piped());
+        cmd.stdout(Stdio::piped());
+        // Server logs go wherever the gateway's own stderr goes
+        cmd.stderr(Stdio::inherit());
+
+        let mut child = cmd.spawn().map_err(|e| AppError::ProcessError(e.to_string()))?;
+        let stdin = child.stdin.take().ok_or_else(|| AppError::ProcessError("No stdin".to_string()))?;
+        let stdout = child.stdout.take().ok_or_else(|| AppError::ProcessError("No stdout".to_string()))?;
+
+        let (tx, messages) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if let Ok(message) = serde_json::from_str::<Value>(&line) {
+                    if tx.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let mut upstream = Upstream {
+            child,
+            stdin,
+            messages,
+            next_id: 1,
+        };
+
+        upstream.request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "mcphub", "version": env!("CARGO_PKG_VERSION") },
+            }),
+        )?;
+        upstream.send(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))?;
+
+        Ok(upstream)
+    }
+
+    fn send(&mut self, message: &Value) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes())?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let message = match self.messages.recv_timeout(remaining) {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(AppError::TimeoutError(format!("No response to {}", method)));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(AppError::ProcessError("Server closed its output".to_string()));
+                }
+            };
+
+            // Requests from the server to us; we offer no client capabilities beyond ping
+            if let (Some(server_method), Some(server_id)) = (message.get("method"), message.get("id")) {
+                let reply = if server_method == "ping" {
+                    rpc_result(server_id, json!({}))
+                } else {
+                    rpc_error(server_id, -32601, "Method not supported by mcphub gateway")
+                };
+                self.send(&reply)?;
+                continue;
+            }
+
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+
+            if let Some(error) = message.get("error") {
+                let text = error.get("message").and_then(Value::as_str).unwrap_or("Unknown error");
+                return Err(AppError::ProcessError(text.to_string()));
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    fn list_tools(&mut self) -> Result<Vec<Value>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let params = match &cursor {
+                Some(c) => json!({ "cursor": c }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params)?;
+            if let Some(page) = result.get("tools").and_then(Value::as_array) {
+                tools.extend(page.iter().cloned());
+            }
+            cursor = result.get("nextCursor").and_then(Value::as_str).map(String::from);
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(tools)
+    }
+}
+
+impl Drop for Upstream {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Aggregates every configured server behind a single MCP endpoint, exposing
+/// their tools as `server__tool`. Each upstream has its own lock, so a slow
+/// call to one server doesn't hold up the others.
+pub struct Gateway {
+    servers: Vec<(String, MCPServer)>,
+    upstreams: OnceLock<HashMap<String, Arc<Mutex<Upstream>>>>,
+    /// Namespaced tool name -> (server name, original tool name)
+    routes: Mutex<HashMap<String, (String, String)>>,
+}
+
+impl Gateway {
+    pub fn new(servers: Vec<(String, MCPServer)>) -> Self {
+        Gateway {
+            servers,
+            upstreams: OnceLock::new(),
+            routes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build a gateway over every server enabled in at least one client,
+    /// plus those `install` moved out of clients, from their canonical definitions.
+    pub fn from_manager(manager: &ConfigManager) -> Result<Self> {
+        let mut servers: Vec<(String, MCPServer)> = manager
+            .get_managed_servers()?
+            .into_iter()
+            .filter(|s| s.name != GATEWAY_SERVER_NAME && s.health != HealthStatus::Disabled)
+            .map(|s| (s.name.clone(), s.to_server()))
+            .collect();
+
+        let registry = Registry::open()?;
+        for name in &manager.get_state().gateway_servers {
+            if servers.iter().any(|(served, _)| served == name) {
+                continue;
+            }
+            if let Some(server) = registry.get(name) {
+                servers.push((name.clone(), server.clone()));
+            }
+        }
+        // Remote servers have nothing to spawn; clients can reach them directly
+        servers.retain(|(_, server)| !server.command.is_empty());
+
+        Ok(Self::new(servers))
+    }
+
+    /// The upstreams, started on first use.
+    fn upstreams(&self) -> &HashMap<String, Arc<Mutex<Upstream>>> {
+        self.upstreams.get_or_init(|| {
+            let mut upstreams = HashMap::new();
+            for (name, server) in &self.servers {
+                match Upstream::spawn(server) {
+                    Ok(upstream) => {
+                        upstreams.insert(name.clone(), Arc::new(Mutex::new(upstream)));
+                    }
+                    Err(e) => eprintln!("mcphub gateway: failed to start {}: {}", name, e),
+                }
+            }
+            upstreams
+        })
+    }
+
+    fn upstream(&self, name: &str) -> Option<Arc<Mutex<Upstream>>> {
+        self.upstreams().get(name).cloned()
+    }
+
+    fn list_tools(&self) -> Vec<Value> {
+        let mut names: Vec<String> = self.upstreams().keys().cloned().collect();
+        names.sort();
+
+        let mut routes = HashMap::new();
+        let mut merged = Vec::new();
+        for name in names {
+            let Some(upstream) = self.upstream(&name) else { continue };
+            let listed = upstream.lock().unwrap_or_else(|e| e.into_inner()).list_tools();
+            let tools = match listed {
+                Ok(tools) => tools,
+                Err(e) => {
+                    eprintln!("mcphub gateway: tools/list failed for {}: {}", name, e);
+                    continue;
+                }
+            };
+
+            for mut tool in tools {
+                let Some(tool_name) = tool.get("name").and_then(Value::as_str).map(String::from) else {
+                    continue;
+                };
+                let namespaced = format!("{}{}{}", name, TOOL_SEPARATOR, tool_name);
+                tool["name"] = Value::String(namespaced.clone());
+                routes.insert(namespaced, (name.clone(), tool_name));
+                merged.push(tool);
+            }
+        }
+
+        *self.routes.lock().unwrap_or_else(|e| e.into_inner()) = routes;
+        merged
+    }
+
+    fn route(&self, namespaced: &str) -> Option<(String, String)> {
+        self.routes.lock().unwrap_or_else(|e| e.into_inner()).get(namespaced).cloned()
+    }
+
+    fn call_tool(&self, mut params: Value) -> Result<Value> {
+        let namespaced = params
+            .get("name")
+            .and_then(Value::as_str)
+            .map(String::from)
+            .ok_or_else(|| AppError::InvalidJson("tools/call requires a tool name".to_string()))?;
+
+        let route = match self.route(&namespaced) {
+            Some(route) => Some(route),
+            None => {
+                self.list_tools();
+                self.route(&namespaced)
+            }
+        };
+        let (server, tool) = route.ok_or_else(|| AppError::ServerNotFound(namespaced.clone()))?;
+        let upstream = self
+            .upstream(&server)
+            .ok_or_else(|| AppError::ServerNotFound(server.clone()))?;
+
+        params["name"] = Value::String(tool);
+        let mut upstream = upstream.lock().unwrap_or_else(|e| e.into_inner());
+        upstream.request("tools/call", params)
+    }
+
+    /// Handle one JSON-RPC message from the client; notifications yield no reply.
+    pub fn handle(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned()?;
+        let method = message.get("method").and_then(Value::as_str).unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or_else(|| json!({}));
+
+        let response = match method {
+            "initialize" => {
+                let version = params
+                    .get("protocolVersion")
+                    .and_then(Value::as_str)
+                    .unwrap_or(PROTOCOL_VERSION);
+                rpc_result(
+                    &id,
+                    json!({
+                        "protocolVersion": version,
+                        "capabilities": { "tools": { "listChanged": false } },
+                        "serverInfo": { "name": "mcphub", "version": env!("CARGO_PKG_VERSION") },
+                    }),
+                )
+            }
+            "ping" => rpc_result(&id, json!({})),
+            "tools/list" => rpc_result(&id, json!({ "tools": self.list_tools() })),
+            "tools/call" => match self.call_tool(params) {
+                Ok(result) => rpc_result(&id, result),
+                Err(AppError::ServerNotFound(name)) => rpc_error(&id, -32602, &format!("Unknown tool: {}", name)),
+                Err(e) => rpc_error(&id, -32603, &e.to_string()),
+            },
+            _ => rpc_error(&id, -32601, &format!("Method not found: {}", method)),
+        };
+
+        Some(response)
+    }
+
+    /// Handle a message or a batch of them. A batch gets an array of the
+    /// replies, or no reply if it held only notifications.
+    pub fn handle_batch(&self, message: Value) -> Option<Value> {
+        let Value::Array(messages) = message else {
+            return self.handle(message);
+        };
+        if messages.is_empty() {
+            return Some(rpc_error(&Value::Null, -32600, "Empty batch"));
+        }
+        let replies: Vec<Value> = messages.into_iter().filter_map(|m| self.handle(m)).collect();
+        (!replies.is_empty()).then_some(Value::Array(replies))
+    }
+}
+
+/// Serve the gateway over stdin/stdout, one JSON-RPC message per line.
+pub fn serve_stdio(gateway: Gateway) -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => gateway.handle_batch(message),
+            Err(e) => Some(rpc_error(&Value::Null, -32700, &e.to_string())),
+        };
+
+        if let Some(reply) = reply {
+            writeln!(stdout, "{}", serde_json::to_string(&reply)?)?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_http(stream: &mut TcpStream, status: &str, body: Option<&Value>) -> Result<()> {
+    let body = body.map(serde_json::to_string).transpose()?.unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Whether a `Host` header or the host of an `Origin` names this machine.
+fn is_loopback_host(host: &str) -> bool {
+    let host = match host.strip_prefix('[') {
+        // [::1]:3737
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    host.eq_ignore_ascii_case("localhost") || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Compare without stopping at the first difference, so timing doesn't give the token away.
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn handle_http(stream: TcpStream, gateway: &Gateway, token: Option<&str>) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers: HashMap<String, String> = HashMap::new();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((key, value)) = header.split_once(':') {
+            headers.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    // Browsers send an Origin; pages elsewhere must not reach local tools
+    if let Some(origin) = headers.get("origin") {
+        let host = origin.split_once("://").map_or("", |(_, rest)| rest);
+        if !is_loopback_host(host) {
+            return write_http(&mut stream, "403 Forbidden", None);
+        }
+    }
+    match token {
+        Some(token) => {
+            let given = headers.get("authorization").and_then(|v| v.strip_prefix("Bearer "));
+            if !given.is_some_and(|given| same_token(given, token)) {
+                return write_http(&mut stream, "401 Unauthorized", None);
+            }
+        }
+        // Without a token only local clients are served; checking Host stops
+        // DNS rebinding from turning a web page into one
+        None => {
+            if !headers.get("host").is_some_and(|host| is_loopback_host(host)) {
+                return write_http(&mut stream, "403 Forbidden", None);
+            }
+        }
+    }
+
+    if path != "/mcp" {
+        return write_http(&mut stream, "404 Not Found", None);
+    }
+    // No server-initiated stream is offered, so only POST is supported
+    if method != "POST" {
+        return write_http(&mut stream, "405 Method Not Allowed", None);
+    }
+    let json = headers.get("content-type").is_some_and(|kind| {
+        kind.split(';').next().unwrap_or_default().trim().eq_ignore_ascii_case("application/json")
+    });
+    if !json {
+        return write_http(&mut stream, "415 Unsupported Media Type", None);
+    }
+    let content_length: usize = headers.get("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    if content_length > MAX_BODY {
+        return write_http(&mut stream, "413 Payload Too Large", None);
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let reply = match serde_json::from_slice::<Value>(&body) {
+        Ok(message) => gateway.handle_batch(message),
+        Err(e) => Some(rpc_error(&Value::Null, -32700, &e.to_string())),
+    };
+
+    match reply {
+        Some(reply) => write_http(&mut stream, "200 OK", Some(&reply)),
+        None => write_http(&mut stream, "202 Accepted", None),
+    }
+}
+
+/// Serve the gateway as a streamable-HTTP MCP endpoint at `http://<addr>/mcp`.
+/// Requests must carry `token` as a bearer token if one is given; without
+/// one, only loopback addresses can be served.
+pub fn serve_http(gateway: Gateway, addr: &str, token: Option<String>) -> Result<()> {
+    let local = addr.to_socket_addrs()?.all(|resolved| resolved.ip().is_loopback());
+    if !local && token.is_none() {
+        return Err(AppError::InvalidArgument(format!(
+            "Serving on {} would let other machines run your tools; set {} to require a bearer token",
+            addr, TOKEN_VARIABLE
+        )));
+    }
+
+    let listener = TcpListener::bind(addr)?;
+    let gateway = Arc::new(gateway);
+    let token: Option<Arc<str>> = token.map(Arc::from);
+    eprintln!("mcphub gateway listening on http://{}/mcp", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let gateway = Arc::clone(&gateway);
+        let token = token.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = handle_http(stream, &gateway, token.as_deref()) {
+                eprintln!("mcphub gateway: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Entry point for `mcphub gateway [--http <addr>]`.
+pub fn run(args: &[String]) -> Result<()> {
+    let gateway = Gateway::from_manager(&ConfigManager::new())?;
+
+    match args.iter().position(|a| a == "--http") {
+        Some(i) => {
+            let addr = args.get(i + 1).map(String::as_str).unwrap_or("127.0.0.1:3737");
+            let token = std::env::var(TOKEN_VARIABLE).ok().filter(|t| !t.is_empty());
+            serve_http(gateway, addr, token)
+        }
+        None => serve_stdio(gateway),
+    }
+}
+
+/// Point `targets` at the gateway so each contains only the `mcphub` entry.
+///
+/// The servers taken out are kept in the registry and the gateway keeps
+/// serving them from there, so every client can be switched over.
+pub fn install(manager: &mut ConfigManager, targets: &[ConfigType]) -> Result<()> {
+    let mut registry = Registry::open()?;
+    let mut moved = Vec::new();
+    for target in targets {
+        let config = manager.read_config(*target)?;
+        for (name, server) in config.servers {
+            if name == GATEWAY_SERVER_NAME {
+                continue;
+            }
+            if registry.get(&name).is_none() {
+                let canonical = interpolate::normalize_server(&recorder::unproxied(&server), *target);
+                registry.set(&HashMap::from([(name.clone(), canonical)]))?;
+            }
+            moved.push(name);
+        }
+    }
+    manager.add_gateway_servers(moved)?;
+
+    let entry = gateway_entry()?;
+    for target in targets {
+        if target.config_path()?.exists() {
+            manager.backup_config(*target)?;
+        }
+        let servers = HashMap::from([(GATEWAY_SERVER_NAME.to_string(), entry.clone())]);
+        manager.write_config(*target, &servers)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::config::{ConfigManager, HealthStatus, MCPServer};
+use crate::supervisor::{resolve_command, server_command};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::process::Command;
+
+/// How long a server gets to start before it's considered up
+const STARTUP_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestResult {
+    pub success: bool,
+    pub message: String,
+    pub output: Option<String>,
+}
+
+/// A test result plus the health it implies, for recording in `AppState`.
+#[derive(Debug)]
+pub struct TestOutcome {
+    pub result: TestResult,
+    pub health: HealthStatus,
+    pub error: Option<String>,
+}
+
+impl TestOutcome {
+    fn healthy(message: &str) -> Self {
+        TestOutcome {
+            result: TestResult {
+                success: true,
+                message: message.to_string(),
+                output: None,
+            },
+            health: HealthStatus::Healthy,
+            error: None,
+        }
+    }
+
+    fn failed(message: String, error: Option<String>, output: Option<String>) -> Self {
+        TestOutcome {
+            result: TestResult {
+                success: false,
+                message,
+                output,
+            },
+            health: HealthStatus::Error,
+            error,
+        }
+    }
+}
+
+/// Start a server briefly to see whether it comes up.
+///
+/// Shared by the GUI's `test_server_connection` and the CLI's `test`; callers
+/// record the outcome's health themselves.
+pub async fn test_server(server: &MCPServer) -> TestOutcome {
+    let expanded_command = ConfigManager::expand_path(&server.command);
+    let expanded_args: Vec<String> = server.args.iter().map(|a| ConfigManager::expand_path(a)).collect();
+
+    // Resolve the command to a full path
+    let Some(command_path) = resolve_command(&expanded_command) else {
+        return TestOutcome::failed(
+            format!("Command not found: {}. Make sure it's installed and in your PATH.", expanded_command),
+            Some(format!("Command not found: {}", expanded_command)),
+            None,
+        );
+    };
+
+    // Check if args contain paths that don't exist
+    for arg in &expanded_args {
+        if arg.starts_with('/') && !arg.contains("://") && !std::path::Path::new(arg).exists() {
+            let message = format!("Path not found: {}", arg);
+            return TestOutcome::failed(message.clone(), Some(message), None);
+        }
+    }
+
+    // Try to start the server briefly, with any secret references resolved
+    let mut cmd = match server_command(&command_path, &expanded_args, &server.env) {
+        Ok(cmd) => Command::from(cmd),
+        Err(e) => return TestOutcome::failed(e.to_string(), Some(e.to_string()), None),
+    };
+
+    // Capture output
+    cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return TestOutcome::failed(format!("Failed to start server: {}", e), Some(e.to_string()), None),
+    };
+
+    // Give it a moment to start
+    tokio::time::sleep(STARTUP_GRACE).await;
+
+    // Check if it's still running (good sign) or exited with error
+    match child.try_wait() {
+        Ok(Some(status)) if status.success() => TestOutcome::healthy("Server started and exited successfully"),
+        Ok(Some(status)) => {
+            let output = child.wait_with_output().await.ok();
+            let stderr = output.map(|o| String::from_utf8_lossy(&o.stderr).to_string());
+            TestOutcome::failed(format!("Server exited with code: {:?}", status.code()), stderr.clone(), stderr)
+        }
+        Ok(None) => {
+            // Still running - this is good, kill it
+            child.kill().await.ok();
+            TestOutcome::healthy("Server started successfully")
+        }
+        Err(e) => TestOutcome::failed(
+            format!("Error checking server status: {}", e),
+            Some(e.to_string()),
+            None,
+        ),
+    }
+}
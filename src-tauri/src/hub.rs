@@ -0,0 +1,649 @@
+use crate::bundle::{self, ExportBundleRequest, ImportBundleRequest};
+use crate::config::{ConfigManager, ConfigType, HealthStatus, MCPServer};
+use crate::daemon;
+use crate::diff;
+use crate::drift;
+use crate::error::{AppError, Result};
+use crate::gateway;
+use crate::health::{self, TestResult};
+use crate::import::{self, ImportRequest};
+use crate::interpolate;
+use crate::marketplace::{self, InstallRequest, MarketplaceIndex, MarketplaceQuery};
+use crate::metadata::{ServerMetadata, ServerQuery};
+use crate::profiles::{self, Profile};
+use crate::recorder::{self, MessageFilter};
+use crate::redact;
+use crate::secrets::SecretVault;
+use crate::signing::{self, BundlePolicy, TrustStore};
+use crate::supervisor::{self, ProcessSupervisor, RunningServer, ServerExit};
+use crate::sync::{self, SyncRule, SyncStrategy};
+use crate::templates::{self, InstantiateRequest, TemplateWatcher, UpgradeRequest};
+use crate::watcher::{self, ConfigWatcher};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// How often the daemon tests every server by default
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveServerRequest {
+    pub name: String,
+    pub server: MCPServer,
+    pub targets: Vec<ConfigType>,
+    pub original_name: Option<String>,
+    /// Replaces the server's metadata; `None` keeps what it has
+    #[serde(default)]
+    pub metadata: Option<ServerMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetEnabledRequest {
+    pub name: String,
+    pub config_type: ConfigType,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRequest {
+    pub name: String,
+    pub source: ConfigType,
+    pub targets: Vec<ConfigType>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingRequest {
+    pub name: String,
+    pub config_type: ConfigType,
+}
+
+/// Payload of the `server-health` event sent after a scheduled check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    pub name: String,
+    pub health: HealthStatus,
+    pub error: Option<String>,
+}
+
+/// Receives background events: the event name and its JSON payload.
+pub type EventSink = Arc<dyn Fn(&str, Value) + Send + Sync>;
+
+/// Which background work runs next to the operations.
+#[derive(Debug, Clone, Copy)]
+pub struct ServiceOptions {
+    /// Publish `config-changed` when a client config changes on disk
+    pub watch: bool,
+    /// Test every server on this interval; `None` disables scheduled checks
+    pub health_interval: Option<Duration>,
+}
+
+/// The single owner of the config manager and process supervisor.
+///
+/// Every Tauri command is an operation here, called by name with the same
+/// arguments the frontend passes to `invoke`, so the GUI, the CLI and the
+/// daemon's socket all share one implementation.
+pub struct Hub {
+    manager: Mutex<ConfigManager>,
+    supervisor: Mutex<ProcessSupervisor>,
+}
+
+impl Default for Hub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn param<T: DeserializeOwned>(params: &Value, key: &str) -> Result<T> {
+    let value = params.get(key).cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|e| AppError::InvalidArgument(format!("{}: {}", key, e)))
+}
+
+fn reply<T: Serialize>(value: T) -> Result<Value> {
+    Ok(serde_json::to_value(value)?)
+}
+
+/// Run a future on its own thread and runtime, so callers don't need to know
+/// whether they're already inside one.
+fn block_on<F>(future: F) -> Result<F::Output>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+                Ok(runtime.block_on(future))
+            })
+            .join()
+            .map_err(|_| AppError::ProcessError("Background task panicked".to_string()))?
+    })
+}
+
+impl Hub {
+    pub fn new() -> Self {
+        Hub {
+            manager: Mutex::new(ConfigManager::new()),
+            supervisor: Mutex::new(ProcessSupervisor::new()),
+        }
+    }
+
+    fn manager(&self) -> Result<MutexGuard<'_, ConfigManager>> {
+        self.manager.lock().map_err(|e| AppError::IoError(e.to_string()))
+    }
+
+    fn supervisor(&self) -> Result<MutexGuard<'_, ProcessSupervisor>> {
+        self.supervisor.lock().map_err(|e| AppError::IoError(e.to_string()))
+    }
+
+    /// Run an operation by its Tauri command name.
+    pub fn call(&self, method: &str, params: &Value) -> Result<Value> {
+        match method {
+            "get_all_configs" => {
+                let manager = self.manager()?;
+                reply(
+                    ConfigType::all()
+                        .into_iter()
+                        .map(|ct| manager.read_config(ct))
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            "get_managed_servers" => {
+                let query: Option<ServerQuery> = param(params, "query")?;
+                let mut servers = self.manager()?.get_managed_servers()?;
+                if let Some(query) = query {
+                    servers.retain(|server| query.matches(server));
+                }
+                reply(servers)
+            }
+            "save_server" => reply(self.save_server(&param(params, "request")?)?),
+            "delete_server" => {
+                let targets: Option<Vec<ConfigType>> = param(params, "targets")?;
+                let targets = targets.unwrap_or_else(ConfigType::all);
+                reply(self.manager()?.remove_server(&param::<String>(params, "name")?, &targets)?)
+            }
+            "rename_server" => reply(
+                self.rename_server(&param::<String>(params, "name")?, &param::<String>(params, "newName")?)?,
+            ),
+            "set_server_metadata" => {
+                let metadata: ServerMetadata = param(params, "metadata")?;
+                reply(self.manager()?.set_server_metadata(&param::<String>(params, "name")?, metadata)?)
+            }
+            "set_server_enabled" => reply(self.set_server_enabled(&param(params, "request")?)?),
+            "test_server_connection" => {
+                reply(self.test_server(&param::<String>(params, "name")?, &param(params, "server")?)?)
+            }
+            "check_variables" => {
+                let request: SaveServerRequest = param(params, "request")?;
+                let servers = HashMap::from([(request.name, request.server.expanded())]);
+                reply(interpolate::check_servers(&servers, &request.targets))
+            }
+            "sync_server" => {
+                let request: SyncRequest = param(params, "request")?;
+                reply(self.manager()?.sync_server(&request.name, request.source, &request.targets)?)
+            }
+            "sync_all_servers" => {
+                let strategy: Option<SyncStrategy> = param(params, "strategy")?;
+                let resolutions: Option<HashMap<String, ConfigType>> = param(params, "resolutions")?;
+                reply(self.manager()?.sync_all(
+                    param(params, "source")?,
+                    strategy.unwrap_or_default(),
+                    &resolutions.unwrap_or_default(),
+                )?)
+            }
+            "preview_sync" => {
+                let strategy: Option<SyncStrategy> = param(params, "strategy")?;
+                let resolutions: Option<HashMap<String, ConfigType>> = param(params, "resolutions")?;
+                reply(sync::plan(
+                    &*self.manager()?,
+                    param(params, "source")?,
+                    strategy.unwrap_or_default(),
+                    &resolutions.unwrap_or_default(),
+                )?)
+            }
+            "set_sync_rule" => {
+                let rule: SyncRule = param(params, "rule")?;
+                reply(self.manager()?.set_sync_rule(&param::<String>(params, "name")?, rule)?)
+            }
+            "get_drift_report" => reply(drift::report(&*self.manager()?)?),
+            "save_profile" => {
+                let mut manager = self.manager()?;
+                let profile = match param::<Option<Profile>>(params, "profile")? {
+                    Some(profile) => profile,
+                    None => profiles::capture(&manager)?,
+                };
+                reply(manager.save_profile(&param::<String>(params, "name")?, profile)?)
+            }
+            "delete_profile" => reply(self.manager()?.delete_profile(&param::<String>(params, "name")?)?),
+            "preview_profile" => reply(profiles::plan(&*self.manager()?, &param::<String>(params, "name")?)?),
+            "apply_profile" => reply(self.manager()?.apply_profile(&param::<String>(params, "name")?)?),
+            "get_active_profile" => reply(profiles::active(&*self.manager()?)?),
+            "list_templates" => reply(templates::catalog(&self.manager()?.get_state().template_dirs)?),
+            "validate_template" => {
+                let values: HashMap<String, String> = param(params, "values")?;
+                let dirs = self.manager()?.get_state().template_dirs.clone();
+                reply(templates::validate(&templates::find(&dirs, &param::<String>(params, "id")?)?, &values))
+            }
+            "set_template_dirs" => reply(self.manager()?.set_template_dirs(param(params, "dirs")?)?),
+            "instantiate_template" => {
+                let request: InstantiateRequest = param(params, "request")?;
+                reply(templates::instantiate(&mut *self.manager()?, &request)?)
+            }
+            "get_template_upgrades" => reply(templates::upgrades(&*self.manager()?)?),
+            "preview_template_upgrade" => {
+                let request: UpgradeRequest = param(params, "request")?;
+                reply(templates::preview_upgrade(&*self.manager()?, &request)?)
+            }
+            "apply_template_upgrade" => {
+                let request: UpgradeRequest = param(params, "request")?;
+                reply(templates::apply_upgrade(&mut *self.manager()?, &request)?)
+            }
+            "refresh_marketplace" => {
+                let source: Option<String> = param(params, "source")?;
+                reply(marketplace::refresh(source.as_deref())?)
+            }
+            "search_marketplace" => {
+                let query: Option<MarketplaceQuery> = param(params, "query")?;
+                reply(marketplace::search(&query.unwrap_or_default())?)
+            }
+            "get_marketplace_template" => {
+                let package: Option<String> = param(params, "package")?;
+                let index = MarketplaceIndex::open()?;
+                let server = index.find(&param::<String>(params, "server")?)?;
+                reply(marketplace::template(server, package.as_deref())?)
+            }
+            "install_from_marketplace" => {
+                let request: InstallRequest = param(params, "request")?;
+                reply(marketplace::install(&mut *self.manager()?, &request)?)
+            }
+            "backup_configs" => {
+                let manager = self.manager()?;
+                let backups: Vec<String> = ConfigType::all()
+                    .into_iter()
+                    .filter_map(|ct| manager.backup_config(ct).ok())
+                    .collect();
+                reply(backups)
+            }
+            "get_app_state" => reply(self.manager()?.get_state()),
+            "diff_configs" => {
+                let (a, b) = (param(params, "a")?, param(params, "b")?);
+                reply(diff::diff_configs(&*self.manager()?, a, b)?)
+            }
+
+            "find_import_placeholders" => {
+                let servers = import::parse_servers(&param::<String>(params, "json")?)?;
+                reply(redact::find_placeholders(&servers))
+            }
+            "preview_import" => {
+                let servers = import::parse_servers(&param::<String>(params, "json")?)?;
+                let targets: Vec<ConfigType> = param(params, "targets")?;
+                reply(import::preview(&*self.manager()?, &servers, &import::everywhere(&servers, &targets))?)
+            }
+            "import_config" => reply(import::import(&*self.manager()?, &param::<ImportRequest>(params, "request")?)?),
+            "export_config" => {
+                let redact: Option<bool> = param(params, "redact")?;
+                reply(self.export_config(param(params, "source")?, redact.unwrap_or(false))?)
+            }
+            "preview_redactions" => {
+                let config = self.manager()?.read_config(param(params, "source")?)?;
+                let servers = ConfigManager::reference_secrets(&config.servers);
+                reply(redact::redact_servers(&servers).1)
+            }
+
+            "export_bundle" => {
+                let request: ExportBundleRequest = param(params, "request")?;
+                let bundle = bundle::export(&*self.manager()?, &request)?;
+                reply(serde_json::to_string_pretty(&bundle)?)
+            }
+            "preview_bundle" => {
+                let targets: Option<Vec<ConfigType>> = param(params, "targets")?;
+                let json: String = param(params, "json")?;
+                reply(bundle::preview(&*self.manager()?, &json, targets.as_deref())?)
+            }
+            "import_bundle" => {
+                let request: ImportBundleRequest = param(params, "request")?;
+                let mut manager = self.manager()?;
+                let policy = manager.get_state().bundle_policy;
+                reply(bundle::import(&mut manager, &request, policy)?)
+            }
+            "get_signing_identity" => reply(signing::identity()?),
+            "generate_signing_key" => {
+                let overwrite: Option<bool> = param(params, "overwrite")?;
+                reply(signing::generate_key(&param::<String>(params, "name")?, overwrite.unwrap_or(false))?)
+            }
+            "sign_bundle" => {
+                let mut bundle = bundle::parse(&param::<String>(params, "json")?)?;
+                signing::sign(&mut bundle)?;
+                reply(serde_json::to_string_pretty(&bundle)?)
+            }
+            "verify_bundle" => {
+                let bundle = bundle::parse(&param::<String>(params, "json")?)?;
+                reply(signing::verify(&bundle, &TrustStore::open()?)?)
+            }
+            "list_trusted_keys" => reply(TrustStore::open()?.list()),
+            "trust_key" => reply(
+                TrustStore::open()?.trust(&param::<String>(params, "name")?, &param::<String>(params, "publicKey")?)?,
+            ),
+            "remove_trusted_key" => reply(TrustStore::open()?.remove(&param::<String>(params, "publicKey")?)?),
+            "set_bundle_policy" => {
+                let policy: BundlePolicy = param(params, "policy")?;
+                reply(self.manager()?.set_bundle_policy(policy)?)
+            }
+
+            "start_server" => {
+                let server: MCPServer = param(params, "server")?;
+                reply(self.supervisor()?.start(&param::<String>(params, "name")?, &server)?)
+            }
+            "stop_server" => reply(self.supervisor()?.stop(&param::<String>(params, "name")?)?),
+            "get_running_servers" => reply(self.supervisor()?.running()),
+            "get_resource_samples" => reply(self.supervisor()?.samples(&param::<String>(params, "name")?)?),
+
+            "get_gateway_entry" => reply(gateway::gateway_entry()?),
+            "install_gateway" => {
+                let targets: Vec<ConfigType> = param(params, "targets")?;
+                reply(gateway::install(&mut *self.manager()?, &targets)?)
+            }
+            "enable_recording" => {
+                let request: RecordingRequest = param(params, "request")?;
+                reply(recorder::enable(&*self.manager()?, &request.name, request.config_type)?)
+            }
+            "disable_recording" => {
+                let request: RecordingRequest = param(params, "request")?;
+                reply(recorder::disable(&*self.manager()?, &request.name, request.config_type)?)
+            }
+            "list_recordings" => {
+                let server: Option<String> = param(params, "server")?;
+                reply(recorder::list_sessions(server.as_deref())?)
+            }
+            "get_recording" => {
+                let filter: Option<MessageFilter> = param(params, "filter")?;
+                reply(recorder::read_session(&param::<String>(params, "id")?, &filter.unwrap_or_default())?)
+            }
+
+            "list_secrets" => reply(SecretVault::open()?.list()),
+            "set_secret" => reply(
+                SecretVault::open()?.set(&param::<String>(params, "name")?, &param::<String>(params, "value")?)?,
+            ),
+            "delete_secret" => reply(SecretVault::open()?.remove(&param::<String>(params, "name")?)?),
+
+            _ => Err(AppError::UnknownMethod(method.to_string())),
+        }
+    }
+
+    fn save_server(&self, request: &SaveServerRequest) -> Result<()> {
+        // Rename in place first, so clients the request doesn't target keep the server
+        if let Some(ref old_name) = request.original_name {
+            if old_name != &request.name {
+                self.rename_server(old_name, &request.name)?;
+            }
+        }
+
+        let mut manager = self.manager()?;
+        manager.add_server(&request.name, &request.server, &request.targets)?;
+        match request.metadata {
+            Some(ref metadata) => manager.set_server_metadata(&request.name, metadata.clone()),
+            None => Ok(()),
+        }
+    }
+
+    fn rename_server(&self, old_name: &str, new_name: &str) -> Result<()> {
+        // Its samples and stop handle are kept under the old name
+        if self.supervisor()?.running().iter().any(|r| r.name == old_name) {
+            return Err(AppError::ProcessError(format!("Stop {} before renaming it", old_name)));
+        }
+        self.manager()?.rename_server(old_name, new_name)
+    }
+
+    fn set_server_enabled(&self, request: &SetEnabledRequest) -> Result<()> {
+        let manager = self.manager()?;
+
+        // Get the server template from another config if enabling
+        let template = if request.enabled {
+            let servers = manager.get_managed_servers()?;
+            servers.iter().find(|s| s.name == request.name).map(|s| s.to_server())
+        } else {
+            None
+        };
+
+        manager.set_server_enabled(&request.name, request.config_type, request.enabled, template.as_ref())
+    }
+
+    /// Test a server without holding the manager lock while it runs.
+    fn test_server(&self, name: &str, server: &MCPServer) -> Result<TestResult> {
+        let outcome = block_on(health::test_server(server))?;
+        self.manager()?.update_server_health(name, outcome.health, outcome.error)?;
+        Ok(outcome.result)
+    }
+
+    fn export_config(&self, source: ConfigType, redact: bool) -> Result<String> {
+        let config = self.manager()?.read_config(source)?;
+        let mut servers = ConfigManager::reference_secrets(&config.servers);
+        if redact {
+            servers = redact::redact_servers(&servers).0;
+        }
+        Ok(serde_json::to_string_pretty(&servers)?)
+    }
+
+    /// Test every local server, recording each result.
+    pub fn check_health(&self) -> Result<Vec<HealthCheck>> {
+        let servers = self.manager()?.get_managed_servers()?;
+
+        let mut checks = Vec::new();
+        for server in servers {
+            // Remote servers have no process to start, and disabled ones aren't in use
+            if server.url.is_some() || server.command.is_empty() || server.health == HealthStatus::Disabled {
+                continue;
+            }
+            let outcome = block_on(health::test_server(&server.to_server()))?;
+            self.manager()?
+                .update_server_health(&server.name, outcome.health, outcome.error.clone())?;
+            checks.push(HealthCheck {
+                name: server.name,
+                health: outcome.health,
+                error: outcome.error,
+            });
+        }
+        Ok(checks)
+    }
+
+    /// Start the background threads: resource sampling, and optionally the
+    /// config watcher and scheduled health checks.
+    pub fn spawn_services(self: &Arc<Self>, options: ServiceOptions, emit: EventSink) {
+        let hub = Arc::clone(self);
+        let sink = Arc::clone(&emit);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(supervisor::SAMPLE_INTERVAL);
+
+            let round = match hub.supervisor.lock() {
+                Ok(mut supervisor) => supervisor.sample_all(),
+                Err(_) => break,
+            };
+            for sample in &round.samples {
+                sink("server-resources", json!(sample));
+            }
+            for exit in &round.exited {
+                sink("server-exited", json!(exit));
+            }
+        });
+
+        if options.watch {
+            let hub = Arc::clone(self);
+            let sink = Arc::clone(&emit);
+            std::thread::spawn(move || {
+                let mut watcher = ConfigWatcher::new();
+                let mut template_watcher = TemplateWatcher::default();
+                loop {
+                    std::thread::sleep(watcher::POLL_INTERVAL);
+                    for change in watcher.poll() {
+                        sink("config-changed", json!(change));
+                    }
+
+                    let Ok(dirs) = hub.manager().map(|m| m.get_state().template_dirs.clone()) else {
+                        break;
+                    };
+                    if template_watcher.poll(&dirs) {
+                        match templates::catalog(&dirs) {
+                            Ok(catalog) => sink("templates-changed", json!(catalog)),
+                            Err(e) => eprintln!("mcphub: reloading templates failed: {}", e),
+                        }
+                    }
+                }
+            });
+        }
+
+        if let Some(interval) = options.health_interval {
+            let hub = Arc::clone(self);
+            std::thread::spawn(move || loop {
+                std::thread::sleep(interval);
+                match hub.check_health() {
+                    Ok(checks) => {
+                        for check in &checks {
+                            emit("server-health", json!(check));
+                        }
+                    }
+                    Err(e) => eprintln!("mcphub: scheduled health check failed: {}", e),
+                }
+            });
+        }
+    }
+}
+
+/// Where operations run: in this process, or in a running daemon.
+#[derive(Clone)]
+pub enum Backend {
+    Local(Arc<Hub>),
+    Daemon(daemon::Client, Arc<Mutex<Fallback>>),
+}
+
+/// What a daemon backend switches to once the daemon can't be reached.
+#[derive(Default)]
+pub struct Fallback {
+    hub: Option<Arc<Hub>>,
+    /// Where `listen` sends events, so an in-process hub can take over
+    emit: Option<EventSink>,
+    /// What the daemon was last seen supervising, reported if it goes away
+    supervised: Vec<RunningServer>,
+}
+
+impl Fallback {
+    /// The in-process hub to use instead of `client`, started the first time
+    /// the daemon isn't listening. Once started it stays in use, so operations
+    /// never split between the two.
+    fn hub(fallback: &Mutex<Fallback>, client: &daemon::Client) -> Option<Arc<Hub>> {
+        let mut fallback = fallback.lock().unwrap_or_else(|e| e.into_inner());
+        if fallback.hub.is_none() && !client.is_listening() {
+            eprintln!("mcphub: the daemon went away, running operations in-process");
+            // Nothing manages what it started any more
+            let orphaned = std::mem::take(&mut fallback.supervised);
+            for server in &orphaned {
+                eprintln!("mcphub: {} (pid {}) may still be running unmanaged", server.name, server.pid);
+            }
+            let hub = Arc::new(Hub::new());
+            if let Some(ref emit) = fallback.emit {
+                emit("daemon-lost", json!({ "orphaned": orphaned }));
+                hub.spawn_services(
+                    ServiceOptions {
+                        watch: true,
+                        health_interval: None,
+                    },
+                    emit.clone(),
+                );
+            }
+            fallback.hub = Some(hub);
+        }
+        fallback.hub.clone()
+    }
+
+    /// Keep up with what the daemon supervises after calls that change it.
+    fn track(fallback: &Mutex<Fallback>, client: &daemon::Client, method: &str, result: &Value) {
+        let running = match method {
+            "get_running_servers" => Ok(result.clone()),
+            "start_server" | "stop_server" => client.call("get_running_servers", Value::Null),
+            _ => return,
+        };
+        if let Some(running) = running.ok().and_then(|r| serde_json::from_value(r).ok()) {
+            fallback.lock().unwrap_or_else(|e| e.into_inner()).supervised = running;
+        }
+    }
+
+    fn exited(fallback: &Mutex<Fallback>, payload: &Value) {
+        if let Ok(exit) = serde_json::from_value::<ServerExit>(payload.clone()) {
+            let mut fallback = fallback.lock().unwrap_or_else(|e| e.into_inner());
+            fallback.supervised.retain(|server| server.pid != exit.pid);
+        }
+    }
+}
+
+impl Backend {
+    /// Use the daemon if one is listening, otherwise run operations in-process.
+    pub fn connect() -> Self {
+        match daemon::Client::connect() {
+            Some(client) => Backend::Daemon(client, Arc::default()),
+            None => Backend::Local(Arc::new(Hub::new())),
+        }
+    }
+
+    pub fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let result = match self {
+            Backend::Local(hub) => hub.call(method, &params)?,
+            Backend::Daemon(client, fallback) => match Fallback::hub(fallback, client) {
+                Some(hub) => hub.call(method, &params)?,
+                None => {
+                    let result = client.call(method, params)?;
+                    Fallback::track(fallback, client, method, &result);
+                    result
+                }
+            },
+        };
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Deliver background events to `emit`. In-process this starts the
+    /// sampler and config watcher; against a daemon it subscribes to the
+    /// daemon's events instead, which include its scheduled health checks.
+    /// If the daemon stops, it subscribes again once it's back, or starts
+    /// the in-process services if it isn't.
+    pub fn listen(&self, emit: EventSink) {
+        match self {
+            Backend::Local(hub) => hub.spawn_services(
+                ServiceOptions {
+                    watch: true,
+                    health_interval: None,
+                },
+                emit,
+            ),
+            Backend::Daemon(client, fallback) => {
+                fallback.lock().unwrap_or_else(|e| e.into_inner()).emit = Some(emit.clone());
+                let client = client.clone();
+                let fallback = fallback.clone();
+                std::thread::spawn(move || loop {
+                    if let Ok(running) = client.call("get_running_servers", Value::Null) {
+                        Fallback::track(&fallback, &client, "get_running_servers", &running);
+                    }
+                    let subscribed = client.subscribe(|event, payload| {
+                        if event == "server-exited" {
+                            Fallback::exited(&fallback, &payload);
+                        }
+                        emit(event, payload)
+                    });
+                    if let Err(e) = subscribed {
+                        eprintln!("mcphub: lost the daemon's events: {}", e);
+                    }
+                    if Fallback::hub(&fallback, &client).is_some() {
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_secs(1));
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use crate::config::{ConfigManager, ConfigType, MCPServer};
+use crate::error::Result;
+use crate::interpolate::validate_servers;
+use crate::plan::{self, ConfigUpdate};
+use crate::recorder;
+use crate::redact;
+use crate::registry::Registry;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Suffix added to a server's name when it is imported alongside an existing one
+const RENAME_SUFFIX: &str = "-imported";
+
+/// What to do when an imported server's name is already taken in a target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportStrategy {
+    Skip,
+    Overwrite,
+    /// Import under a free name such as `github-imported`
+    Rename,
+    /// Overwrite only if the imported file is newer than the target config
+    KeepNewest,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportOptions {
+    /// Per-server strategy; servers not listed use `default_strategy`
+    #[serde(default)]
+    pub strategies: HashMap<String, ImportStrategy>,
+    /// Defaults to `skip`
+    pub default_strategy: Option<ImportStrategy>,
+    /// Values for `mcphub-placeholder://` references
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    /// When the imported file was last modified, used by `keepNewest`
+    pub source_modified: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRequest {
+    /// A bare server map or a whole client file with `mcpServers`
+    pub json: String,
+    pub targets: Vec<ConfigType>,
+    #[serde(flatten)]
+    pub options: ImportOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCollision {
+    pub name: String,
+    pub config_type: ConfigType,
+    pub existing: MCPServer,
+    pub incoming: MCPServer,
+    /// The same definition is already there, so importing changes nothing
+    pub identical: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub servers: Vec<String>,
+    pub placeholders: Vec<String>,
+    pub collisions: Vec<ImportCollision>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportAction {
+    Added,
+    Overwritten,
+    Renamed,
+    Skipped,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportChange {
+    pub name: String,
+    /// The name written to the target, if anything was written
+    pub written_as: Option<String>,
+    pub config_type: ConfigType,
+    pub action: ImportAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub changes: Vec<ImportChange>,
+    pub backups: Vec<String>,
+}
+
+/// Parse servers from either a bare `{name: server}` map or a client file.
+pub fn parse_servers(json: &str) -> Result<HashMap<String, MCPServer>> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let servers = match value.get("mcpServers") {
+        Some(servers) if servers.is_object() => servers.clone(),
+        _ => value,
+    };
+    Ok(serde_json::from_value(servers)?)
+}
+
+fn sorted_names(servers: &HashMap<String, MCPServer>) -> Vec<String> {
+    let mut names: Vec<String> = servers.keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Which clients each server goes to when every server goes to the same ones.
+pub fn everywhere(servers: &HashMap<String, MCPServer>, targets: &[ConfigType]) -> HashMap<String, Vec<ConfigType>> {
+    servers.keys().map(|name| (name.clone(), targets.to_vec())).collect()
+}
+
+fn targets_of(placement: &HashMap<String, Vec<ConfigType>>) -> Vec<ConfigType> {
+    ConfigType::all()
+        .into_iter()
+        .filter(|ct| placement.values().any(|targets| targets.contains(ct)))
+        .collect()
+}
+
+/// Show which imported servers would collide with existing ones where they'd be placed.
+pub fn preview(
+    manager: &ConfigManager,
+    servers: &HashMap<String, MCPServer>,
+    placement: &HashMap<String, Vec<ConfigType>>,
+) -> Result<ImportPreview> {
+    let mut collisions = Vec::new();
+
+    for config_type in targets_of(placement) {
+        let config = manager.read_config(config_type)?;
+        for name in sorted_names(servers) {
+            if !placement.get(&name).is_some_and(|t| t.contains(&config_type)) {
+                continue;
+            }
+            if let Some(existing) = config.servers.get(&name).or_else(|| config.disabled.get(&name)) {
+                let incoming = servers[&name].expanded();
+                collisions.push(ImportCollision {
+                    identical: recorder::unproxied(existing) == incoming,
+                    name,
+                    config_type,
+                    existing: existing.clone(),
+                    incoming,
+                });
+            }
+        }
+    }
+
+    Ok(ImportPreview {
+        placeholders: redact::find_placeholders(servers),
+        servers: sorted_names(servers),
+        collisions,
+    })
+}
+
+fn free_name(name: &str, taken: &HashSet<String>) -> String {
+    let base = format!("{}{}", name, RENAME_SUFFIX);
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while taken.contains(&candidate) {
+        candidate = format!("{}-{}", base, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// Merge the imported servers into the clients `placement` assigns them to.
+pub fn merge(
+    manager: &ConfigManager,
+    mut servers: HashMap<String, MCPServer>,
+    placement: &HashMap<String, Vec<ConfigType>>,
+    options: &ImportOptions,
+) -> Result<ImportReport> {
+    // Everything that could fail is checked before the first write
+    redact::fill_placeholders(&mut servers, &options.values)?;
+    let servers: HashMap<String, MCPServer> = servers.into_iter().map(|(n, s)| (n, s.expanded())).collect();
+    for (name, server) in &servers {
+        let targets = placement.get(name).cloned().unwrap_or_default();
+        validate_servers(&HashMap::from([(name.clone(), server.clone())]), &targets)?;
+    }
+
+    let default_strategy = options.default_strategy.unwrap_or(ImportStrategy::Skip);
+    let mut changes = Vec::new();
+    let mut updates = Vec::new();
+    let mut written = HashMap::new();
+
+    let mut configs = Vec::new();
+    for config_type in targets_of(placement) {
+        configs.push((config_type, manager.read_config(config_type)?));
+    }
+    // A renamed server gets the same new name in every client, free of
+    // enabled and disabled copies alike
+    let mut taken: HashSet<String> = servers.keys().cloned().collect();
+    for (_, config) in &configs {
+        taken.extend(config.servers.keys().cloned());
+        taken.extend(config.disabled.keys().cloned());
+    }
+    let mut renamed = HashMap::new();
+    for name in sorted_names(&servers) {
+        if options.strategies.get(&name).copied().unwrap_or(default_strategy) == ImportStrategy::Rename {
+            let new_name = free_name(&name, &taken);
+            taken.insert(new_name.clone());
+            renamed.insert(name, new_name);
+        }
+    }
+
+    for (config_type, config) in configs {
+        let mut merged = config.servers.clone();
+        let mut disabled = config.disabled.clone();
+        let mut changed = false;
+
+        for name in sorted_names(&servers) {
+            if !placement.get(&name).is_some_and(|t| t.contains(&config_type)) {
+                continue;
+            }
+            let incoming = &servers[&name];
+            let strategy = options.strategies.get(&name).copied().unwrap_or(default_strategy);
+
+            let existing = config.servers.get(&name).or_else(|| config.disabled.get(&name));
+            let (action, written_as) = match existing {
+                None => (ImportAction::Added, Some(name.clone())),
+                Some(existing) if recorder::unproxied(existing) == *incoming => (ImportAction::Unchanged, None),
+                Some(_) => match strategy {
+                    ImportStrategy::Skip => (ImportAction::Skipped, None),
+                    ImportStrategy::Overwrite => (ImportAction::Overwritten, Some(name.clone())),
+                    ImportStrategy::Rename => (ImportAction::Renamed, Some(renamed[&name].clone())),
+                    ImportStrategy::KeepNewest => {
+                        // Without a source timestamp the existing entry wins
+                        let newer = match (options.source_modified, config.last_modified) {
+                            (Some(source), Some(target)) => source > target,
+                            (Some(_), None) => true,
+                            (None, _) => false,
+                        };
+                        if newer {
+                            (ImportAction::Overwritten, Some(name.clone()))
+                        } else {
+                            (ImportAction::Skipped, None)
+                        }
+                    }
+                },
+            };
+
+            if let Some(ref target_name) = written_as {
+                // Overwriting a recorded copy keeps it recorded, and a disabled one disabled
+                let current = config.servers.get(target_name).or_else(|| config.disabled.get(target_name));
+                let server = recorder::proxied_like(current, incoming.clone());
+                if disabled.contains_key(target_name) && !merged.contains_key(target_name) {
+                    disabled.insert(target_name.clone(), server);
+                } else {
+                    merged.insert(target_name.clone(), server);
+                }
+                written.insert(target_name.clone(), incoming.clone());
+                changed = true;
+            }
+            changes.push(ImportChange {
+                name,
+                written_as,
+                config_type,
+                action,
+            });
+        }
+
+        if changed {
+            updates.push(ConfigUpdate {
+                before: config,
+                servers: merged,
+                disabled,
+            });
+        }
+    }
+
+    let backups = plan::write_updates(manager, updates)?;
+    // What was imported is now the canonical definition
+    Registry::open()?.set(&written)?;
+
+    Ok(ImportReport { changes, backups })
+}
+
+/// Import a server map or client file into the request's targets.
+pub fn import(manager: &ConfigManager, request: &ImportRequest) -> Result<ImportReport> {
+    let servers = parse_servers(&request.json)?;
+    let placement = everywhere(&servers, &request.targets);
+    merge(manager, servers, &placement, &request.options)
+}
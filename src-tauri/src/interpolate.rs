@@ -0,0 +1,305 @@
+use crate::config::{ConfigType, MCPServer};
+use crate::error::{AppError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How a client treats `${...}` variables in its config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolationSyntax {
+    /// Understands `${env:NAME}` itself (Roo Code)
+    EnvPrefixed,
+    /// Expands shell-style `${NAME}` (Claude Code)
+    Shell,
+    /// Needs literal values (Claude Desktop)
+    Literal,
+}
+
+/// A variable in mcphub's canonical syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Variable {
+    /// `${env:NAME}`
+    Env(String),
+    /// `${userHome}`
+    UserHome,
+    /// `${workspaceFolder}`, which has no value for user-level configs
+    WorkspaceFolder,
+}
+
+impl Variable {
+    fn parse(inner: &str) -> Option<Variable> {
+        match inner {
+            "userHome" => Some(Variable::UserHome),
+            "workspaceFolder" => Some(Variable::WorkspaceFolder),
+            _ => inner
+                .strip_prefix("env:")
+                .filter(|name| is_env_name(name))
+                .map(|name| Variable::Env(name.to_string())),
+        }
+    }
+
+    fn canonical(&self) -> String {
+        match self {
+            Variable::Env(name) => format!("${{env:{}}}", name),
+            Variable::UserHome => "${userHome}".to_string(),
+            Variable::WorkspaceFolder => "${workspaceFolder}".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterpolationIssue {
+    pub server: String,
+    pub config_type: ConfigType,
+    /// e.g. `args[2]`, `env.GITHUB_TOKEN`, `url`
+    pub field: String,
+    pub variable: String,
+    pub message: String,
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Split `value` into literal text and `${...}` segments.
+fn segments(value: &str) -> Vec<(bool, &str)> {
+    let mut parts = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else { break };
+        if start > 0 {
+            parts.push((false, &rest[..start]));
+        }
+        parts.push((true, &rest[start + 2..start + len]));
+        rest = &rest[start + len + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push((false, rest));
+    }
+    parts
+}
+
+/// Render one value for a client, or explain which variables can't be resolved.
+pub fn render_value(value: &str, syntax: InterpolationSyntax) -> std::result::Result<String, Vec<(String, String)>> {
+    if !value.contains("${") {
+        return Ok(value.to_string());
+    }
+
+    let mut rendered = String::new();
+    let mut problems = Vec::new();
+
+    for (is_variable, text) in segments(value) {
+        let variable = if is_variable { Variable::parse(text) } else { None };
+        let Some(variable) = variable else {
+            // Anything that isn't canonical syntax passes through untouched
+            if is_variable {
+                rendered.push_str(&format!("${{{}}}", text));
+            } else {
+                rendered.push_str(text);
+            }
+            continue;
+        };
+
+        match (&variable, syntax) {
+            (Variable::Env(_), InterpolationSyntax::EnvPrefixed) => rendered.push_str(&variable.canonical()),
+            (Variable::Env(name), InterpolationSyntax::Shell) => rendered.push_str(&format!("${{{}}}", name)),
+            (Variable::Env(name), InterpolationSyntax::Literal) => match std::env::var(name) {
+                Ok(v) => rendered.push_str(&v),
+                Err(_) => problems.push((variable.canonical(), format!("{} is not set in mcphub's environment", name))),
+            },
+            (Variable::UserHome, _) => match dirs::home_dir() {
+                Some(home) => rendered.push_str(&home.to_string_lossy()),
+                None => problems.push((variable.canonical(), "Could not find home directory".to_string())),
+            },
+            (Variable::WorkspaceFolder, _) => problems.push((
+                variable.canonical(),
+                "There is no workspace folder for a user-level config".to_string(),
+            )),
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(rendered)
+    } else {
+        Err(problems)
+    }
+}
+
+/// Render every interpolated field of a server for `config_type`.
+pub fn render_server(
+    name: &str,
+    server: &MCPServer,
+    config_type: ConfigType,
+) -> std::result::Result<MCPServer, Vec<InterpolationIssue>> {
+    let syntax = config_type.interpolation();
+    let mut rendered = server.clone();
+    let mut issues = Vec::new();
+
+    let mut render = |field: String, value: &mut String| match render_value(value, syntax) {
+        Ok(v) => *value = v,
+        Err(problems) => issues.extend(problems.into_iter().map(|(variable, message)| InterpolationIssue {
+            server: name.to_string(),
+            config_type,
+            field: field.clone(),
+            variable,
+            message,
+        })),
+    };
+
+    render("command".to_string(), &mut rendered.command);
+    for (i, arg) in rendered.args.iter_mut().enumerate() {
+        render(format!("args[{}]", i), arg);
+    }
+    for (key, value) in rendered.env.iter_mut() {
+        render(format!("env.{}", key), value);
+    }
+    if let Some(ref mut url) = rendered.url {
+        render("url".to_string(), url);
+    }
+    for (key, value) in rendered.headers.iter_mut() {
+        render(format!("headers.{}", key), value);
+    }
+
+    if issues.is_empty() {
+        Ok(rendered)
+    } else {
+        Err(issues)
+    }
+}
+
+fn describe(issues: &[InterpolationIssue]) -> String {
+    issues
+        .iter()
+        .map(|i| format!("{} {} ({}): {}", i.server, i.field, i.variable, i.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Render a whole server map for `config_type`, failing on the first unresolved variable set.
+pub fn render_servers(servers: &HashMap<String, MCPServer>, config_type: ConfigType) -> Result<HashMap<String, MCPServer>> {
+    let mut rendered = HashMap::new();
+    let mut issues = Vec::new();
+
+    for (name, server) in servers {
+        match render_server(name, server, config_type) {
+            Ok(server) => {
+                rendered.insert(name.clone(), server);
+            }
+            Err(mut found) => issues.append(&mut found),
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(rendered)
+    } else {
+        Err(AppError::UnresolvedVariables(describe(&issues)))
+    }
+}
+
+/// Every variable in `servers` that can't be rendered for one of `targets`.
+pub fn check_servers(servers: &HashMap<String, MCPServer>, targets: &[ConfigType]) -> Vec<InterpolationIssue> {
+    let mut issues = Vec::new();
+    for config_type in targets {
+        for (name, server) in servers {
+            if let Err(mut found) = render_server(name, server, *config_type) {
+                issues.append(&mut found);
+            }
+        }
+    }
+    issues.sort_by(|a, b| (&a.server, &a.field).cmp(&(&b.server, &b.field)));
+    issues
+}
+
+/// Fail with every unresolved variable before anything is written to `targets`.
+pub fn validate_servers(servers: &HashMap<String, MCPServer>, targets: &[ConfigType]) -> Result<()> {
+    let issues = check_servers(servers, targets);
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::UnresolvedVariables(describe(&issues)))
+    }
+}
+
+/// Resolve a value to a literal for a server mcphub launches itself.
+pub fn render_for_spawn(value: &str) -> Result<String> {
+    render_value(value, InterpolationSyntax::Literal).map_err(|problems| {
+        AppError::UnresolvedVariables(
+            problems
+                .into_iter()
+                .map(|(variable, message)| format!("{}: {}", variable, message))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    })
+}
+
+/// Translate a client's own variable dialect back into canonical syntax.
+pub fn normalize_value(value: &str, syntax: InterpolationSyntax) -> String {
+    if syntax != InterpolationSyntax::Shell || !value.contains("${") {
+        return value.to_string();
+    }
+
+    segments(value)
+        .into_iter()
+        .map(|(is_variable, text)| match is_variable {
+            true if is_env_name(text) => Variable::Env(text.to_string()).canonical(),
+            true => format!("${{{}}}", text),
+            false => text.to_string(),
+        })
+        .collect()
+}
+
+pub fn normalize_server(server: &MCPServer, config_type: ConfigType) -> MCPServer {
+    let syntax = config_type.interpolation();
+    let mut normalized = server.clone();
+
+    normalized.command = normalize_value(&normalized.command, syntax);
+    for arg in normalized.args.iter_mut() {
+        *arg = normalize_value(arg, syntax);
+    }
+    for value in normalized.env.values_mut().chain(normalized.headers.values_mut()) {
+        *value = normalize_value(value, syntax);
+    }
+    if let Some(ref mut url) = normalized.url {
+        *url = normalize_value(url, syntax);
+    }
+
+    normalized
+}
+
+/// A client's copy in canonical syntax, given the canonical copies mcphub
+/// already knows of. Variables a client can't expand were written to it as
+/// literals: every `${env:NAME}` for Claude Desktop, `${userHome}` for all.
+/// Where a known value renders for the client to exactly the copy's value,
+/// the known value is taken back; everything else is normalized as usual.
+pub fn canonical_copy(copy: &MCPServer, known: &[&MCPServer], config_type: ConfigType) -> MCPServer {
+    let syntax = config_type.interpolation();
+    let mut canonical = normalize_server(copy, config_type);
+    let recover = |value: &mut String, written: &str, candidates: Vec<Option<&String>>| {
+        let found = candidates
+            .into_iter()
+            .flatten()
+            .find(|c| c.contains("${") && render_value(c, syntax).as_deref() == Ok(written));
+        if let Some(found) = found {
+            *value = found.clone();
+        }
+    };
+
+    recover(&mut canonical.command, &copy.command, known.iter().map(|k| Some(&k.command)).collect());
+    for (i, arg) in canonical.args.iter_mut().enumerate() {
+        recover(arg, &copy.args[i], known.iter().map(|k| k.args.get(i)).collect());
+    }
+    for (key, value) in canonical.env.iter_mut() {
+        recover(value, &copy.env[key], known.iter().map(|k| k.env.get(key)).collect());
+    }
+    for (key, value) in canonical.headers.iter_mut() {
+        recover(value, &copy.headers[key], known.iter().map(|k| k.headers.get(key)).collect());
+    }
+    if let (Some(url), Some(written)) = (canonical.url.as_mut(), copy.url.as_ref()) {
+        recover(url, written, known.iter().map(|k| k.url.as_ref()).collect());
+    }
+
+    canonical
+}
@@ -1,8 +1,34 @@
-mod config;
+pub mod config;
+mod cli;
 mod commands;
-mod error;
+pub mod error;
+pub mod bundle;
+pub mod daemon;
+pub mod diff;
+pub mod drift;
+pub mod gateway;
+pub mod health;
+pub mod hub;
+pub mod import;
+pub mod interpolate;
+pub mod marketplace;
+pub mod metadata;
+pub mod monitor;
+pub mod plan;
+pub mod profiles;
+pub mod recorder;
+pub mod redact;
+pub mod registry;
+pub mod secrets;
+pub mod signing;
+pub mod stash;
+pub mod supervisor;
+pub mod sync;
+pub mod templates;
+pub mod watcher;
 
-use tauri::Manager;
+use std::sync::Arc;
+use tauri::{Emitter, Manager};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -15,26 +41,126 @@ pub fn run() {
             commands::get_managed_servers,
             commands::save_server,
             commands::delete_server,
+            commands::rename_server,
+            commands::set_server_metadata,
             commands::set_server_enabled,
             commands::test_server_connection,
+            commands::check_variables,
             commands::sync_server,
             commands::sync_all_servers,
+            commands::preview_sync,
+            commands::set_sync_rule,
             commands::backup_configs,
             commands::get_app_state,
+            commands::diff_configs,
+            commands::get_drift_report,
+            commands::save_profile,
+            commands::delete_profile,
+            commands::preview_profile,
+            commands::apply_profile,
+            commands::get_active_profile,
+            commands::list_templates,
+            commands::set_template_dirs,
+            commands::validate_template,
+            commands::instantiate_template,
+            commands::get_template_upgrades,
+            commands::preview_template_upgrade,
+            commands::apply_template_upgrade,
+            commands::refresh_marketplace,
+            commands::search_marketplace,
+            commands::get_marketplace_template,
+            commands::install_from_marketplace,
+            commands::preview_import,
             commands::import_config,
             commands::export_config,
+            commands::find_import_placeholders,
+            commands::preview_redactions,
+            commands::export_bundle,
+            commands::preview_bundle,
+            commands::import_bundle,
+            commands::get_signing_identity,
+            commands::generate_signing_key,
+            commands::sign_bundle,
+            commands::verify_bundle,
+            commands::list_trusted_keys,
+            commands::trust_key,
+            commands::remove_trusted_key,
+            commands::set_bundle_policy,
+            commands::start_server,
+            commands::stop_server,
+            commands::get_running_servers,
+            commands::get_resource_samples,
+            commands::get_gateway_entry,
+            commands::install_gateway,
+            commands::enable_recording,
+            commands::disable_recording,
+            commands::list_recordings,
+            commands::get_recording,
+            commands::list_secrets,
+            commands::set_secret,
+            commands::delete_secret,
         ])
         .setup(|app| {
             // Set up app state directory
             let app_data_dir = app.path().app_data_dir().expect("Failed to get app data dir");
             std::fs::create_dir_all(&app_data_dir).ok();
 
-            // Initialize config manager
-            let config_manager = config::ConfigManager::new();
-            app.manage(std::sync::Mutex::new(config_manager));
+            // Run operations through the daemon if one is up, otherwise in-process,
+            // and forward background events (resources, exits, config changes) to the UI
+            let backend = hub::Backend::connect();
+            let handle = app.handle().clone();
+            backend.listen(Arc::new(move |event, payload| {
+                handle.emit(event, payload).ok();
+            }));
+            app.manage(backend);
 
             Ok(())
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Run a command-line subcommand instead of the GUI; `args` starts with the
+/// program name. Returns the exit code.
+pub fn run_cli(args: &[String]) -> i32 {
+    match cli::run(args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("mcphub-cli: {}", e);
+            1
+        }
+    }
+}
+
+/// Run the aggregating MCP gateway instead of the GUI; returns the exit code.
+pub fn run_gateway(args: &[String]) -> i32 {
+    match gateway::run(args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("mcphub gateway: {}", e);
+            1
+        }
+    }
+}
+
+/// Run headless with the control socket instead of the GUI; returns the exit code.
+pub fn run_daemon(args: &[String]) -> i32 {
+    match daemon::run(args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("mcphub daemon: {}", e);
+            1
+        }
+    }
+}
+
+/// Run the JSON-RPC recording shim around a server; returns the server's exit code.
+pub fn run_recorder(args: &[String]) -> i32 {
+    match recorder::run(args) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("mcphub record: {}", e);
+            1
+        }
+    }
+}
@@ -1,6 +1,37 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+/// Release builds on Windows start without a console, so the command-line
+/// modes borrow the one they were started from, if any.
+#[cfg(all(windows, not(debug_assertions)))]
+fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(all(windows, not(debug_assertions))))]
+fn attach_console() {}
+
 fn main() {
-    mcphub_lib::run()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // macOS passes a process serial number when launched from Finder
+    if args.first().is_some_and(|arg| !arg.starts_with("-psn_")) {
+        attach_console();
+    }
+    match args.first().map(String::as_str) {
+        Some("gateway") => std::process::exit(mcphub_lib::run_gateway(&args[1..])),
+        Some("daemon") => std::process::exit(mcphub_lib::run_daemon(&args[1..])),
+        Some("record") => std::process::exit(mcphub_lib::run_recorder(&args[1..])),
+        Some(arg) if !arg.starts_with("-psn_") => {
+            std::process::exit(mcphub_lib::run_cli(&std::env::args().collect::<Vec<_>>()))
+        }
+        _ => mcphub_lib::run(),
+    }
 }
@@ -0,0 +1,761 @@
+use crate::config::{ConfigManager, ConfigType, MCPServer};
+use crate::error::{AppError, Result};
+use crate::templates::{self, ParameterKind, ServerTemplate, TemplateParameter};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Where publishers put their own fields in a registry entry's `_meta`.
+const PUBLISHER_META: &str = "io.modelcontextprotocol.registry/publisher-provided";
+/// Where the official registry says which version of a server is the latest.
+const OFFICIAL_META: &str = "io.modelcontextprotocol.registry/official";
+
+/// A value a server needs: an env var, a header, or the value of an argument.
+/// With no fixed `value`, the user fills it in when installing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Input {
+    /// Env var or header name; unused for positional arguments
+    #[serde(default)]
+    pub name: String,
+    pub description: Option<String>,
+    /// A fixed value, which may use `{variable}`s from `variables`
+    pub value: Option<String>,
+    pub default: Option<String>,
+    #[serde(default)]
+    pub is_required: bool,
+    #[serde(default)]
+    pub is_secret: bool,
+    /// `string`, `number`, `boolean` or `filepath`
+    pub format: Option<String>,
+    #[serde(default)]
+    pub choices: Vec<String>,
+    #[serde(default)]
+    pub variables: HashMap<String, Input>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArgumentKind {
+    Positional,
+    Named,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Argument {
+    #[serde(rename = "type")]
+    pub kind: ArgumentKind,
+    /// Label for the value of a positional argument
+    pub value_hint: Option<String>,
+    #[serde(flatten)]
+    pub input: Input,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transport {
+    /// `stdio`, `streamable-http` or `sse`
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub headers: Vec<Input>,
+}
+
+/// A package the server can be run from, such as an npm or PyPI package or a
+/// Docker image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Package {
+    /// `npm`, `pypi`, `oci`, `nuget` or `mcpb`
+    pub registry_type: String,
+    pub identifier: String,
+    pub version: Option<String>,
+    /// The command to run the package with, e.g. `npx`
+    pub runtime_hint: Option<String>,
+    #[serde(default)]
+    pub transport: Transport,
+    #[serde(default)]
+    pub runtime_arguments: Vec<Argument>,
+    #[serde(default)]
+    pub package_arguments: Vec<Argument>,
+    #[serde(default)]
+    pub environment_variables: Vec<Input>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Repository {
+    pub url: Option<String>,
+    pub source: Option<String>,
+}
+
+/// A server in a registry index, in the shape of the MCP registry's `server.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketplaceServer {
+    /// Reverse-DNS name, e.g. `io.github.acme/weather`
+    pub name: String,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    pub version: Option<String>,
+    pub repository: Option<Repository>,
+    pub website_url: Option<String>,
+    #[serde(default)]
+    pub packages: Vec<Package>,
+    /// Hosted endpoints to connect to instead of running a package
+    #[serde(default)]
+    pub remotes: Vec<Transport>,
+    /// From the entry's own `tags`, or the publisher's `_meta`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(rename = "_meta", default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Value>,
+}
+
+impl MarketplaceServer {
+    /// Server name to install as when none is given: the part after the namespace.
+    pub fn short_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+
+    fn is_latest(&self) -> Option<bool> {
+        self.meta.as_ref()?.get(OFFICIAL_META)?.get("isLatest")?.as_bool()
+    }
+}
+
+/// Filters for `search`. Every given filter must match, ignoring case.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketplaceQuery {
+    /// Text in the name, title or description
+    pub name: Option<String>,
+    pub tag: Option<String>,
+    /// Text in a package identifier, e.g. `server-github`
+    pub package: Option<String>,
+}
+
+impl MarketplaceQuery {
+    pub fn matches(&self, server: &MarketplaceServer) -> bool {
+        let contains = |value: &str, text: &str| value.to_lowercase().contains(&text.to_lowercase());
+        let named = match self.name {
+            Some(ref text) => {
+                contains(&server.name, text)
+                    || server.title.as_deref().is_some_and(|t| contains(t, text))
+                    || contains(&server.description, text)
+            }
+            None => true,
+        };
+        let tagged = match self.tag {
+            Some(ref tag) => server.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            None => true,
+        };
+        let packaged = match self.package {
+            Some(ref text) => server.packages.iter().any(|p| contains(&p.identifier, text)),
+            None => true,
+        };
+        named && tagged && packaged
+    }
+}
+
+/// The downloaded index, kept in `~/.mcphub/marketplace.json` so searching
+/// and installing work offline.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketplaceIndex {
+    /// Path or URL the index was loaded from
+    pub source: Option<String>,
+    pub fetched_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub servers: Vec<MarketplaceServer>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallRequest {
+    /// Registry name of the server, e.g. `io.github.acme/weather`
+    pub server: String,
+    /// Name to install it as; the part of the registry name after the namespace if omitted
+    pub name: Option<String>,
+    /// Package identifier or remote URL to install from; the first package
+    /// that can be installed, else the first remote, if omitted
+    pub package: Option<String>,
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    pub targets: Vec<ConfigType>,
+}
+
+fn index_path() -> Result<PathBuf> {
+    let dir = dirs::home_dir()
+        .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+        .join(".mcphub");
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join("marketplace.json"))
+}
+
+impl MarketplaceIndex {
+    /// The last downloaded index; empty if there is none yet.
+    pub fn open() -> Result<Self> {
+        let path = index_path()?;
+        if !path.exists() {
+            return Ok(MarketplaceIndex::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(&path)?).unwrap_or_default())
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::write(index_path()?, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn find(&self, name: &str) -> Result<&MarketplaceServer> {
+        self.servers
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| AppError::InvalidArgument(format!("No server named {} in the marketplace", name)))
+    }
+}
+
+/// Read an index from a file, or download it with `curl`.
+fn read_source(source: &str) -> Result<String> {
+    if !(source.starts_with("http://") || source.starts_with("https://")) {
+        return Ok(fs::read_to_string(ConfigManager::expand_path(source))?);
+    }
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--max-time", "30", source])
+        .output()
+        .map_err(|e| AppError::ProcessError(format!("Could not run curl: {}", e)))?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(AppError::IoError(format!("Downloading {} failed: {}", source, message)));
+    }
+    String::from_utf8(output.stdout).map_err(|e| AppError::InvalidJson(e.to_string()))
+}
+
+/// Parse an index: a list of `server.json` entries, or a registry API
+/// response whose `servers` are entries or `{ "server": ..., "_meta": ... }`
+/// wrappers. Only one version of each server is kept, the one marked latest
+/// if any, else the last one listed.
+pub fn parse_index(content: &str) -> Result<Vec<MarketplaceServer>> {
+    let value: Value = serde_json::from_str(content)?;
+    let listed = match value {
+        Value::Array(items) => items,
+        Value::Object(mut object) => match object.remove("servers") {
+            Some(Value::Array(items)) => items,
+            _ => return Err(AppError::InvalidJson("Expected a list of servers".to_string())),
+        },
+        _ => return Err(AppError::InvalidJson("Expected a list of servers".to_string())),
+    };
+
+    let mut servers: Vec<MarketplaceServer> = Vec::new();
+    for item in listed {
+        let mut server: MarketplaceServer = match item {
+            Value::Object(mut wrapper) if wrapper.contains_key("server") => {
+                let mut server: MarketplaceServer = serde_json::from_value(wrapper.remove("server").unwrap_or_default())?;
+                if let Some(meta) = wrapper.remove("_meta") {
+                    server.meta = Some(meta);
+                }
+                server
+            }
+            item => serde_json::from_value(item)?,
+        };
+        let publisher_tags = server.meta.as_ref().and_then(|m| m.get(PUBLISHER_META)?.get("tags")?.as_array().cloned());
+        for tag in publisher_tags.unwrap_or_default().iter().filter_map(|t| t.as_str()) {
+            if !server.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                server.tags.push(tag.to_string());
+            }
+        }
+
+        match servers.iter().position(|s| s.name == server.name) {
+            Some(i) if servers[i].is_latest() == Some(true) && server.is_latest() != Some(true) => {}
+            Some(i) => servers[i] = server,
+            None => servers.push(server),
+        }
+    }
+    Ok(servers)
+}
+
+/// Load the index from `source`, or from where it was last loaded, and keep
+/// it for offline use.
+pub fn refresh(source: Option<&str>) -> Result<MarketplaceIndex> {
+    let source = match source {
+        Some(source) => source.to_string(),
+        None => MarketplaceIndex::open()?
+            .source
+            .ok_or_else(|| AppError::InvalidArgument("No marketplace index has been loaded yet".to_string()))?,
+    };
+    let index = MarketplaceIndex {
+        servers: parse_index(&read_source(&source)?)?,
+        source: Some(source),
+        fetched_at: Some(Utc::now()),
+    };
+    index.save()?;
+    Ok(index)
+}
+
+/// Servers in the downloaded index that match `query`, sorted by name.
+pub fn search(query: &MarketplaceQuery) -> Result<Vec<MarketplaceServer>> {
+    let mut servers: Vec<MarketplaceServer> =
+        MarketplaceIndex::open()?.servers.into_iter().filter(|s| query.matches(s)).collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(servers)
+}
+
+/// Turns registry inputs into template parameters and `{{placeholder}}` values.
+#[derive(Default)]
+struct Form {
+    parameters: Vec<TemplateParameter>,
+}
+
+impl Form {
+    fn parameter(&mut self, name: &str, input: &Input, in_env: bool) -> String {
+        if !self.parameters.iter().any(|p| p.name == name) {
+            let kind = if !input.choices.is_empty() {
+                ParameterKind::Enum
+            } else if input.is_secret && in_env {
+                ParameterKind::Secret
+            } else if input.format.as_deref() == Some("filepath") {
+                ParameterKind::Path
+            } else {
+                ParameterKind::Text
+            };
+            self.parameters.push(TemplateParameter {
+                name: name.to_string(),
+                label: name.to_string(),
+                kind,
+                description: input.description.clone(),
+                required: input.is_required,
+                default: input.default.clone(),
+                options: input.choices.clone(),
+                schemes: Vec::new(),
+            });
+        }
+        format!("{{{{{}}}}}", name)
+    }
+
+    /// The value for an input: its fixed value with `{variable}`s turned into
+    /// parameters, else a parameter named `name`. Secrets are only kept out of
+    /// the config in env vars, so elsewhere they're plain text.
+    fn value(&mut self, name: &str, input: &Input, in_env: bool) -> String {
+        let Some(ref value) = input.value else {
+            return self.parameter(name, input, in_env);
+        };
+        let mut value = value.clone();
+        let mut variables: Vec<(&String, &Input)> = input.variables.iter().collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+        for (variable, variable_input) in variables {
+            let placeholder = self.parameter(variable, variable_input, in_env);
+            value = value.replace(&format!("{{{}}}", variable), &placeholder);
+        }
+        value
+    }
+
+    fn arguments(&mut self, arguments: &[Argument]) -> Vec<String> {
+        let mut args = Vec::new();
+        for (i, argument) in arguments.iter().enumerate() {
+            let input = &argument.input;
+            match argument.kind {
+                ArgumentKind::Positional => {
+                    let name = argument.value_hint.clone().unwrap_or_else(|| format!("arg{}", i + 1));
+                    args.push(self.value(&name, input, false));
+                }
+                // An optional flag is joined to its value so it's dropped when left empty
+                ArgumentKind::Named if input.value.is_none() && !input.is_required => {
+                    let value = self.value(input.name.trim_start_matches('-'), input, false);
+                    args.push(format!("{}={}", input.name, value));
+                }
+                ArgumentKind::Named => {
+                    args.push(input.name.clone());
+                    if input.value.is_some() || input.is_required {
+                        args.push(self.value(input.name.trim_start_matches('-'), input, false));
+                    }
+                }
+            }
+        }
+        args
+    }
+}
+
+/// The command and leading args that run a package. A runtime hint picks the
+/// runner when it's one this knows how to drive; other hints get the default.
+fn runner(package: &Package, runtime_args: Vec<String>, env: &[Input]) -> Result<(String, Vec<String>)> {
+    let version = package.version.as_deref().filter(|v| !v.is_empty());
+    let hint = package.runtime_hint.as_deref();
+    let identifier = package.identifier.clone();
+    let (command, leading, spec) = match package.registry_type.as_str() {
+        "npm" => {
+            let spec = match version {
+                Some(version) => format!("{}@{}", identifier, version),
+                None => identifier,
+            };
+            match hint {
+                Some("bunx") => ("bunx", Vec::new(), vec![spec]),
+                Some("pnpm" | "pnpx") => ("pnpm", vec!["dlx".to_string()], vec![spec]),
+                Some("yarn") => ("yarn", vec!["dlx".to_string()], vec![spec]),
+                // npx asks before installing unless told not to
+                _ if runtime_args.iter().any(|a| a == "-y" || a == "--yes") => ("npx", Vec::new(), vec![spec]),
+                _ => ("npx", vec!["-y".to_string()], vec![spec]),
+            }
+        }
+        "pypi" => match (hint, version) {
+            // pipx runs an app by name; a pinned version comes from --spec
+            (Some("pipx"), Some(version)) => (
+                "pipx",
+                vec!["run".to_string()],
+                vec!["--spec".to_string(), format!("{}=={}", identifier, version), identifier],
+            ),
+            (Some("pipx"), None) => ("pipx", vec!["run".to_string()], vec![identifier]),
+            (_, Some(version)) => ("uvx", Vec::new(), vec![format!("{}=={}", identifier, version)]),
+            (_, None) => ("uvx", Vec::new(), vec![identifier]),
+        },
+        "oci" => {
+            let tagged = identifier.rsplit('/').next().is_some_and(|last| last.contains(':'));
+            let spec = match version {
+                Some(version) if !tagged => format!("{}:{}", identifier, version),
+                _ => identifier,
+            };
+            // Env vars only reach the container when passed through with -e
+            let mut leading = vec!["run".to_string(), "-i".to_string(), "--rm".to_string()];
+            for variable in env {
+                leading.extend(["-e".to_string(), variable.name.clone()]);
+            }
+            let command = if hint == Some("podman") { "podman" } else { "docker" };
+            (command, leading, vec![spec])
+        }
+        other => {
+            return Err(AppError::InvalidArgument(format!(
+                "{} packages can't be installed yet; choose another package or a remote",
+                other
+            )))
+        }
+    };
+
+    let mut args = leading;
+    args.extend(runtime_args);
+    args.extend(spec);
+    Ok((command.to_string(), args))
+}
+
+fn is_stdio(transport: &Transport) -> bool {
+    transport.kind.is_empty() || transport.kind == "stdio"
+}
+
+/// The form and server definition for installing `server` from `package`, a
+/// package identifier or remote URL.
+pub fn template(server: &MarketplaceServer, package: Option<&str>) -> Result<ServerTemplate> {
+    let chosen_package = match package {
+        Some(choice) => server.packages.iter().find(|p| p.identifier == choice),
+        None => server
+            .packages
+            .iter()
+            .find(|p| is_stdio(&p.transport) && matches!(p.registry_type.as_str(), "npm" | "pypi" | "oci")),
+    };
+    let chosen_remote = match package {
+        Some(choice) => server.remotes.iter().find(|r| r.url.as_deref() == Some(choice)),
+        None => server.remotes.first(),
+    };
+
+    let mut form = Form::default();
+    let definition = if let Some(package) = chosen_package {
+        if !is_stdio(&package.transport) {
+            return Err(AppError::InvalidArgument(format!(
+                "{} serves over {}; run it yourself and add its URL",
+                package.identifier, package.transport.kind
+            )));
+        }
+        let runtime_args = form.arguments(&package.runtime_arguments);
+        let (command, mut args) = runner(package, runtime_args, &package.environment_variables)?;
+        args.extend(form.arguments(&package.package_arguments));
+        let env = package
+            .environment_variables
+            .iter()
+            .map(|variable| (variable.name.clone(), form.value(&variable.name, variable, true)))
+            .collect();
+        MCPServer {
+            command,
+            args,
+            env,
+            url: None,
+            headers: HashMap::new(),
+            always_allow: Vec::new(),
+        }
+    } else if let Some(remote) = chosen_remote {
+        let url = remote.url.clone().unwrap_or_default();
+        let headers = remote
+            .headers
+            .iter()
+            .map(|header| (header.name.clone(), form.value(&header.name, header, false)))
+            .collect();
+        MCPServer {
+            command: String::new(),
+            args: Vec::new(),
+            env: HashMap::new(),
+            url: Some(url),
+            headers,
+            always_allow: Vec::new(),
+        }
+    } else {
+        let message = match package {
+            Some(choice) => format!("{} has no package or remote {}", server.name, choice),
+            None => format!("{} has no package or remote that can be installed", server.name),
+        };
+        return Err(AppError::InvalidArgument(message));
+    };
+
+    Ok(ServerTemplate {
+        id: server.name.clone(),
+        version: 1,
+        name: server.title.clone().unwrap_or_else(|| server.name.clone()),
+        description: server.description.clone(),
+        source_url: server
+            .repository
+            .as_ref()
+            .and_then(|r| r.url.clone())
+            .or_else(|| server.website_url.clone()),
+        parameters: form.parameters,
+        server: definition,
+        source: None,
+        overrides_builtin: false,
+    })
+}
+
+/// Add a server from the downloaded index to the chosen clients, filled in
+/// like a template. Its registry tags become the server's tags.
+pub fn install(manager: &mut ConfigManager, request: &InstallRequest) -> Result<MCPServer> {
+    let index = MarketplaceIndex::open()?;
+    let server = index.find(&request.server)?;
+    let template = template(server, request.package.as_deref())?;
+    let name = request.name.clone().unwrap_or_else(|| server.short_name().to_string());
+    let (installed, _) = templates::add_from_template(manager, &template, &name, &request.values, &request.targets)?;
+
+    if !server.tags.is_empty() {
+        let mut metadata = manager.get_state().metadata.get(&name).cloned().unwrap_or_default();
+        metadata.tags.extend(server.tags.iter().cloned());
+        manager.set_server_metadata(&name, metadata)?;
+    }
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(value: Value) -> Vec<MarketplaceServer> {
+        parse_index(&value.to_string()).unwrap()
+    }
+
+    fn server(value: Value) -> MarketplaceServer {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn latest(is_latest: bool) -> Value {
+        json!({ OFFICIAL_META: { "isLatest": is_latest } })
+    }
+
+    fn kind(template: &ServerTemplate, name: &str) -> ParameterKind {
+        template.parameters.iter().find(|p| p.name == name).unwrap().kind
+    }
+
+    #[test]
+    fn parses_a_bare_list() {
+        let servers = parse(json!([
+            { "name": "io.github.acme/weather", "description": "Forecasts", "tags": ["api"] },
+            { "name": "io.github.acme/notes" },
+        ]));
+        let names: Vec<&str> = servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["io.github.acme/weather", "io.github.acme/notes"]);
+        assert_eq!(servers[0].tags, ["api"]);
+        assert_eq!(servers[1].short_name(), "notes");
+    }
+
+    #[test]
+    fn unwraps_an_api_response() {
+        let servers = parse(json!({
+            "servers": [{
+                "server": { "name": "io.github.acme/weather", "tags": ["api"] },
+                "_meta": { PUBLISHER_META: { "tags": ["API", "forecast"] } },
+            }],
+            "metadata": { "count": 1 },
+        }));
+        assert_eq!(servers.len(), 1);
+        assert!(servers[0].meta.is_some());
+        assert_eq!(servers[0].tags, ["api", "forecast"]);
+    }
+
+    #[test]
+    fn rejects_an_index_without_servers() {
+        assert!(parse_index(&json!({ "items": [] }).to_string()).is_err());
+        assert!(parse_index("\"servers\"").is_err());
+    }
+
+    #[test]
+    fn keeps_the_latest_version() {
+        let servers = parse(json!({ "servers": [
+            { "server": { "name": "acme/weather", "version": "1.0.0" }, "_meta": latest(false) },
+            { "server": { "name": "acme/weather", "version": "2.0.0" }, "_meta": latest(true) },
+            { "server": { "name": "acme/weather", "version": "1.5.0" }, "_meta": latest(false) },
+        ]}));
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].version.as_deref(), Some("2.0.0"));
+    }
+
+    #[test]
+    fn keeps_the_last_version_when_none_is_marked_latest() {
+        let servers = parse(json!([
+            { "name": "acme/weather", "version": "1.0.0" },
+            { "name": "acme/weather", "version": "1.1.0" },
+        ]));
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].version.as_deref(), Some("1.1.0"));
+    }
+
+    #[test]
+    fn maps_npm_arguments_and_env() {
+        let entry = server(json!({
+            "name": "acme/files",
+            "packages": [{
+                "registryType": "npm",
+                "identifier": "@acme/files",
+                "version": "1.2.0",
+                "packageArguments": [
+                    { "type": "positional", "valueHint": "root", "isRequired": true, "format": "filepath" },
+                    { "type": "named", "name": "--mode", "isRequired": true, "choices": ["ro", "rw"] },
+                    { "type": "named", "name": "--log-level" },
+                    { "type": "named", "name": "--region", "value": "{region}",
+                      "variables": { "region": { "default": "eu" } } },
+                ],
+                "environmentVariables": [
+                    { "name": "FILES_TOKEN", "isSecret": true, "isRequired": true },
+                ],
+            }],
+        }));
+        let template = template(&entry, None).unwrap();
+
+        assert_eq!(template.server.command, "npx");
+        assert_eq!(
+            template.server.args,
+            [
+                "-y",
+                "@acme/files@1.2.0",
+                "{{root}}",
+                "--mode",
+                "{{mode}}",
+                "--log-level={{log-level}}",
+                "--region",
+                "{{region}}",
+            ]
+        );
+        assert_eq!(template.server.env["FILES_TOKEN"], "{{FILES_TOKEN}}");
+        assert_eq!(kind(&template, "root"), ParameterKind::Path);
+        assert_eq!(kind(&template, "mode"), ParameterKind::Enum);
+        assert_eq!(kind(&template, "FILES_TOKEN"), ParameterKind::Secret);
+        let region = template.parameters.iter().find(|p| p.name == "region").unwrap();
+        assert_eq!(region.default.as_deref(), Some("eu"));
+    }
+
+    #[test]
+    fn passes_env_into_oci_containers() {
+        let entry = server(json!({
+            "name": "acme/db",
+            "packages": [{
+                "registryType": "oci",
+                "identifier": "ghcr.io/acme/db",
+                "version": "3",
+                "environmentVariables": [{ "name": "DB_URL", "isRequired": true }],
+            }],
+        }));
+        let template = template(&entry, None).unwrap();
+        assert_eq!(template.server.command, "docker");
+        assert_eq!(template.server.args, ["run", "-i", "--rm", "-e", "DB_URL", "ghcr.io/acme/db:3"]);
+        assert_eq!(template.server.env["DB_URL"], "{{DB_URL}}");
+    }
+
+    #[test]
+    fn runs_pypi_packages_with_the_runtime_hint() {
+        let entry = server(json!({
+            "name": "acme/py",
+            "packages": [{ "registryType": "pypi", "identifier": "acme-py", "version": "0.3", "runtimeHint": "pipx" }],
+        }));
+        let template = template(&entry, None).unwrap();
+        assert_eq!(template.server.command, "pipx");
+        assert_eq!(template.server.args, ["run", "--spec", "acme-py==0.3", "acme-py"]);
+    }
+
+    #[test]
+    fn keeps_npx_unattended_alongside_runtime_arguments() {
+        let entry = server(json!({
+            "name": "acme/files",
+            "packages": [{
+                "registryType": "npm",
+                "identifier": "@acme/files",
+                "runtimeHint": "npx",
+                "runtimeArguments": [{ "type": "named", "name": "--node-options", "value": "--max-old-space-size=512" }],
+            }],
+        }));
+        let template = template(&entry, None).unwrap();
+        assert_eq!(template.server.command, "npx");
+        assert_eq!(template.server.args, ["-y", "--node-options", "--max-old-space-size=512", "@acme/files"]);
+    }
+
+    #[test]
+    fn keeps_secret_headers_out_of_the_vault() {
+        let entry = server(json!({
+            "name": "acme/hosted",
+            "remotes": [{
+                "type": "streamable-http",
+                "url": "https://mcp.acme.dev",
+                "headers": [{ "name": "Authorization", "value": "Bearer {token}",
+                              "variables": { "token": { "isSecret": true, "isRequired": true } } }],
+            }],
+        }));
+        let template = template(&entry, None).unwrap();
+        assert_eq!(template.server.url.as_deref(), Some("https://mcp.acme.dev"));
+        assert_eq!(template.server.headers["Authorization"], "Bearer {{token}}");
+        // Only env values can reference the vault
+        assert_eq!(kind(&template, "token"), ParameterKind::Text);
+    }
+
+    #[test]
+    fn downloads_an_index_over_http() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let body = json!([{ "name": "io.github.acme/weather" }]).to_string();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/index.json", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let read = stream.read(&mut request).unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request[..read]).into_owned()
+        });
+
+        let servers = parse_index(&read_source(&url).unwrap()).unwrap();
+        assert!(server.join().unwrap().starts_with("GET /index.json "));
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].short_name(), "weather");
+    }
+
+    #[test]
+    fn refuses_packages_it_cannot_run() {
+        let entry = server(json!({
+            "name": "acme/dotnet",
+            "packages": [{ "registryType": "nuget", "identifier": "Acme.Mcp" }],
+        }));
+        assert!(template(&entry, Some("Acme.Mcp")).is_err());
+        assert!(template(&entry, None).is_err());
+    }
+}
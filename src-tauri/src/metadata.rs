@@ -0,0 +1,92 @@
+use crate::config::ManagedServer;
+use serde::{Deserialize, Serialize};
+
+/// mcphub's own notes about a server, kept in `~/.mcphub/state.json` by server
+/// name. None of it is written to client configs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerMetadata {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub group: Option<String>,
+    pub description: Option<String>,
+    pub owner: Option<String>,
+    /// Where the server comes from, e.g. its repository or package page
+    pub source_url: Option<String>,
+    pub notes: Option<String>,
+}
+
+fn tidy(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl ServerMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == ServerMetadata::default()
+    }
+
+    /// Trimmed, with blank values dropped and tags deduplicated case-insensitively.
+    pub fn normalized(self) -> Self {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.to_string());
+            }
+        }
+        ServerMetadata {
+            tags,
+            group: tidy(self.group),
+            description: tidy(self.description),
+            owner: tidy(self.owner),
+            source_url: tidy(self.source_url),
+            notes: tidy(self.notes),
+        }
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// Filters for `get_managed_servers`. Every given filter must match, ignoring case.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerQuery {
+    /// Servers with all of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub group: Option<String>,
+    pub owner: Option<String>,
+    /// Text in the name, tags, description or notes
+    pub text: Option<String>,
+}
+
+fn same(wanted: &Option<String>, value: &Option<String>) -> bool {
+    match wanted {
+        Some(wanted) => value.as_ref().is_some_and(|v| v.eq_ignore_ascii_case(wanted)),
+        None => true,
+    }
+}
+
+fn mentions(server: &ManagedServer, text: &str) -> bool {
+    let metadata = &server.metadata;
+    let contains = |value: &str| value.to_lowercase().contains(text);
+    contains(&server.name)
+        || metadata.tags.iter().any(|tag| contains(tag))
+        || metadata.description.as_deref().is_some_and(contains)
+        || metadata.notes.as_deref().is_some_and(contains)
+}
+
+impl ServerQuery {
+    pub fn matches(&self, server: &ManagedServer) -> bool {
+        let metadata = &server.metadata;
+        self.tags.iter().all(|tag| metadata.has_tag(tag))
+            && same(&self.group, &metadata.group)
+            && same(&self.owner, &metadata.owner)
+            && match self.text {
+                Some(ref text) => mentions(server, &text.to_lowercase()),
+                None => true,
+            }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Raw, cumulative resource counters for a process tree at one point in time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessSnapshot {
+    /// Total CPU time (user + system) consumed so far, in seconds
+    pub cpu_seconds: f64,
+    pub rss_bytes: u64,
+    /// `None` when the backend has no cheap way to count descriptors
+    pub open_fds: Option<u32>,
+    pub child_processes: u32,
+}
+
+/// A single point in a server's resource time series.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceSample {
+    pub timestamp: DateTime<Utc>,
+    pub pid: u32,
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub open_fds: Option<u32>,
+    pub child_processes: u32,
+}
+
+/// Backend that knows how to read resource usage for a process on the current OS.
+///
+/// Counters cover the whole process tree rooted at `pid`, since launchers like
+/// `npx` or `uvx` usually hand the real work off to a child process.
+pub trait ResourceSampler: Send {
+    fn sample(&self, pid: u32) -> Result<ProcessSnapshot>;
+}
+
+/// Pick the best sampler available on this platform.
+pub fn default_sampler() -> Box<dyn ResourceSampler> {
+    #[cfg(target_os = "linux")]
+    {
+        Box::new(ProcfsSampler)
+    }
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        Box::new(PsSampler)
+    }
+    #[cfg(not(unix))]
+    {
+        Box::new(UnsupportedSampler)
+    }
+}
+
+/// Collect `root` and all of its descendants from a list of `(pid, ppid)` pairs.
+fn process_tree(root: u32, processes: &[(u32, u32)]) -> Vec<u32> {
+    let mut tree = vec![root];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        for (pid, ppid) in processes {
+            if *ppid == parent && !tree.contains(pid) {
+                tree.push(*pid);
+            }
+        }
+        i += 1;
+    }
+    tree
+}
+
+/// Reads `/proc/<pid>` on Linux.
+#[cfg(target_os = "linux")]
+pub struct ProcfsSampler;
+
+#[cfg(target_os = "linux")]
+impl ProcfsSampler {
+    /// Kernel clock ticks per second, the unit of CPU times in `/proc`
+    fn clock_ticks() -> f64 {
+        extern "C" {
+            fn sysconf(name: std::os::raw::c_int) -> std::os::raw::c_long;
+        }
+        const SC_CLK_TCK: std::os::raw::c_int = 2;
+        let ticks = unsafe { sysconf(SC_CLK_TCK) };
+        // 100 on every mainstream configuration, should the query ever fail
+        if ticks > 0 {
+            ticks as f64
+        } else {
+            100.0
+        }
+    }
+
+    /// Returns `(ppid, utime + stime ticks)` from `/proc/<pid>/stat`.
+    fn read_stat(pid: u32) -> Option<(u32, u64)> {
+        let content = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The command name may contain spaces, so split after its closing paren
+        let rest = &content[content.rfind(')')? + 1..];
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let ppid = fields.get(1)?.parse().ok()?;
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+        Some((ppid, utime + stime))
+    }
+
+    fn read_rss(pid: u32) -> u64 {
+        std::fs::read_to_string(format!("/proc/{}/status", pid))
+            .ok()
+            .and_then(|status| {
+                status
+                    .lines()
+                    .find(|l| l.starts_with("VmRSS:"))
+                    .and_then(|l| l.split_whitespace().nth(1))
+                    .and_then(|kb| kb.parse::<u64>().ok())
+            })
+            .map(|kb| kb * 1024)
+            .unwrap_or(0)
+    }
+
+    fn count_fds(pid: u32) -> u32 {
+        std::fs::read_dir(format!("/proc/{}/fd", pid))
+            .map(|entries| entries.count() as u32)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl ResourceSampler for ProcfsSampler {
+    fn sample(&self, pid: u32) -> Result<ProcessSnapshot> {
+        if Self::read_stat(pid).is_none() {
+            return Err(AppError::ProcessError(format!("Process {} is not running", pid)));
+        }
+
+        let processes: Vec<(u32, u32)> = std::fs::read_dir("/proc")?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().to_str().and_then(|n| n.parse::<u32>().ok()))
+            .filter_map(|p| Self::read_stat(p).map(|(ppid, _)| (p, ppid)))
+            .collect();
+
+        let tree = process_tree(pid, &processes);
+        let mut snapshot = ProcessSnapshot {
+            child_processes: (tree.len() - 1) as u32,
+            open_fds: Some(0),
+            ..Default::default()
+        };
+
+        for p in tree {
+            if let Some((_, ticks)) = Self::read_stat(p) {
+                snapshot.cpu_seconds += ticks as f64 / Self::clock_ticks();
+            }
+            snapshot.rss_bytes += Self::read_rss(p);
+            snapshot.open_fds = snapshot.open_fds.map(|n| n + Self::count_fds(p));
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// Shells out to `ps` on macOS and the BSDs, which have no procfs.
+#[cfg(all(unix, not(target_os = "linux")))]
+pub struct PsSampler;
+
+#[cfg(all(unix, not(target_os = "linux")))]
+impl PsSampler {
+    /// Parse `ps` cputime of the form `[[dd-]hh:]mm:ss[.cc]` into seconds.
+    fn parse_cputime(value: &str) -> f64 {
+        let (days, clock) = match value.split_once('-') {
+            Some((d, rest)) => (d.parse::<f64>().unwrap_or(0.0), rest),
+            None => (0.0, value),
+        };
+        let seconds = clock
+            .split(':')
+            .fold(0.0, |acc, part| acc * 60.0 + part.parse::<f64>().unwrap_or(0.0));
+        days * 86_400.0 + seconds
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+impl ResourceSampler for PsSampler {
+    fn sample(&self, pid: u32) -> Result<ProcessSnapshot> {
+        let output = std::process::Command::new("ps")
+            .args(["-A", "-o", "pid=,ppid=,rss=,time="])
+            .output()
+            .map_err(|e| AppError::ProcessError(e.to_string()))?;
+
+        let rows: Vec<(u32, u32, u64, f64)> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let cols: Vec<&str> = line.split_whitespace().collect();
+                Some((
+                    cols.first()?.parse().ok()?,
+                    cols.get(1)?.parse().ok()?,
+                    cols.get(2)?.parse().ok()?,
+                    Self::parse_cputime(cols.get(3)?),
+                ))
+            })
+            .collect();
+
+        if !rows.iter().any(|r| r.0 == pid) {
+            return Err(AppError::ProcessError(format!("Process {} is not running", pid)));
+        }
+
+        let pairs: Vec<(u32, u32)> = rows.iter().map(|r| (r.0, r.1)).collect();
+        let tree = process_tree(pid, &pairs);
+        let mut snapshot = ProcessSnapshot {
+            child_processes: (tree.len() - 1) as u32,
+            ..Default::default()
+        };
+
+        for row in rows.iter().filter(|r| tree.contains(&r.0)) {
+            // ps reports RSS in kilobytes
+            snapshot.rss_bytes += row.2 * 1024;
+            snapshot.cpu_seconds += row.3;
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// Fallback for platforms without a sampling backend.
+#[cfg(not(unix))]
+pub struct UnsupportedSampler;
+
+#[cfg(not(unix))]
+impl ResourceSampler for UnsupportedSampler {
+    fn sample(&self, _pid: u32) -> Result<ProcessSnapshot> {
+        Err(AppError::ProcessError(
+            "Resource monitoring is not supported on this platform".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,212 @@
+use crate::config::{ConfigManager, ConfigType, MCPConfig, MCPServer};
+use crate::diff::{self, FieldChange};
+use crate::error::Result;
+use crate::interpolate::validate_servers;
+use crate::recorder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlanAction {
+    Add,
+    Update,
+    Remove,
+    /// Bring back the client's disabled copy, or add the canonical definition
+    Enable,
+    /// Keep the client's copy aside as disabled
+    Disable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedChange {
+    pub server: String,
+    pub config_type: ConfigType,
+    pub action: PlanAction,
+    pub before: Option<MCPServer>,
+    pub after: Option<MCPServer>,
+    /// Field-level changes for updates, masked like `diff_configs`
+    pub fields: Vec<FieldChange>,
+    /// Why the change is planned, e.g. "Newest in Roo Code"
+    pub reason: String,
+}
+
+impl PlannedChange {
+    /// The change turning `before` into `after`, or `None` if they're equivalent.
+    pub fn between(
+        server: &str,
+        config_type: ConfigType,
+        before: Option<&MCPServer>,
+        after: Option<&MCPServer>,
+        reason: String,
+    ) -> Option<Self> {
+        let (action, fields) = match (before, after) {
+            (None, None) => return None,
+            (None, Some(_)) => (PlanAction::Add, Vec::new()),
+            (Some(_), None) => (PlanAction::Remove, Vec::new()),
+            (Some(b), Some(a)) => {
+                let fields = diff::diff_servers(b, a);
+                if fields.is_empty() {
+                    return None;
+                }
+                (PlanAction::Update, fields)
+            }
+        };
+        Some(PlannedChange {
+            server: server.to_string(),
+            config_type,
+            action,
+            before: before.cloned(),
+            after: after.cloned(),
+            fields,
+            reason,
+        })
+    }
+}
+
+/// One client's copy of a conflicting server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictVersion {
+    pub config_type: ConfigType,
+    /// `None` if the client deleted the server
+    pub server: Option<MCPServer>,
+    /// Changes from the baseline, masked like `diff_configs`
+    pub fields: Vec<FieldChange>,
+}
+
+/// A server changed in different ways in several clients since the last sync.
+/// Each client keeps its own copy until the conflict is resolved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conflict {
+    pub server: String,
+    /// The server as of the last sync, if it existed then
+    pub base: Option<MCPServer>,
+    /// Only the clients whose copy differs from the baseline
+    pub versions: Vec<ConflictVersion>,
+}
+
+/// Changes to client configs, computed up front so they can be previewed
+/// and then applied as a unit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePlan {
+    pub changes: Vec<PlannedChange>,
+    /// Servers left untouched because they need a decision
+    #[serde(default)]
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ChangePlan {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Clients the plan would write.
+    pub fn targets(&self) -> Vec<ConfigType> {
+        let mut targets: Vec<ConfigType> = Vec::new();
+        for change in &self.changes {
+            if !targets.contains(&change.config_type) {
+                targets.push(change.config_type);
+            }
+        }
+        targets
+    }
+
+    /// Plan the changes that turn each client's `current` servers into `desired`.
+    /// Clients missing from `desired` are left alone.
+    pub fn from_states(
+        current: &HashMap<ConfigType, HashMap<String, MCPServer>>,
+        desired: &HashMap<ConfigType, HashMap<String, MCPServer>>,
+        reason: impl Fn(&str, ConfigType, Option<&MCPServer>) -> String,
+    ) -> Self {
+        let empty = HashMap::new();
+        let mut changes = Vec::new();
+        for config_type in ConfigType::all() {
+            let Some(wanted) = desired.get(&config_type) else {
+                continue;
+            };
+            let existing = current.get(&config_type).unwrap_or(&empty);
+
+            let names: BTreeSet<&String> = existing.keys().chain(wanted.keys()).collect();
+            for name in names {
+                let after = wanted.get(name);
+                let why = reason(name, config_type, after);
+                changes.extend(PlannedChange::between(name, config_type, existing.get(name), after, why));
+            }
+        }
+        ChangePlan {
+            changes,
+            conflicts: Vec::new(),
+        }
+    }
+}
+
+/// Apply a plan to the client configs, backing each one up before its first write.
+///
+/// Every resulting config is checked for variables it can't render before any
+/// file is touched. If a write fails anyway, the clients already written are
+/// put back as they were. Returns the backup paths.
+pub fn apply(manager: &ConfigManager, plan: &ChangePlan) -> Result<Vec<String>> {
+    let mut results = Vec::new();
+    for config_type in plan.targets() {
+        let before = manager.read_config(config_type)?;
+        let (mut servers, mut disabled) = (before.servers.clone(), before.disabled.clone());
+        for change in plan.changes.iter().filter(|c| c.config_type == config_type) {
+            let name = &change.server;
+            match (change.action, &change.after) {
+                (PlanAction::Disable, _) => {
+                    if let Some(server) = servers.remove(name) {
+                        disabled.insert(name.clone(), server);
+                    }
+                }
+                (PlanAction::Remove, _) | (_, None) => {
+                    servers.remove(name);
+                }
+                (_, Some(server)) => {
+                    // A recorded copy stays recorded
+                    let server = recorder::proxied_like(before.servers.get(name), server.expanded());
+                    servers.insert(name.clone(), server);
+                    disabled.remove(name);
+                }
+            }
+        }
+        validate_servers(&servers, &[config_type])?;
+        results.push(ConfigUpdate {
+            before,
+            servers,
+            disabled,
+        });
+    }
+    write_updates(manager, results)
+}
+
+/// The servers a client config should end up with.
+pub struct ConfigUpdate {
+    pub before: MCPConfig,
+    pub servers: HashMap<String, MCPServer>,
+    pub disabled: HashMap<String, MCPServer>,
+}
+
+/// Write every update, backing each config up first. If a write fails, the
+/// configs already written are put back as they were. Returns the backup paths.
+pub fn write_updates(manager: &ConfigManager, updates: Vec<ConfigUpdate>) -> Result<Vec<String>> {
+    let mut backups = Vec::new();
+    let mut written: Vec<MCPConfig> = Vec::new();
+    for update in updates {
+        let config_type = update.before.config_type;
+        if let Ok(path) = manager.backup_config(config_type) {
+            backups.push(path);
+        }
+        if let Err(e) = manager.write_servers(config_type, &update.servers, &update.disabled) {
+            for config in written.iter().rev() {
+                manager.write_servers(config.config_type, &config.servers, &config.disabled).ok();
+            }
+            return Err(e);
+        }
+        written.push(update.before);
+    }
+    Ok(backups)
+}
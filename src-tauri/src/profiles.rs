@@ -0,0 +1,103 @@
+use crate::config::{ConfigManager, ConfigType, MCPConfig};
+use crate::error::{AppError, Result};
+use crate::plan::{ChangePlan, PlanAction, PlannedChange};
+use crate::registry::Registry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A named setup such as "work" or "demo": which clients each server is enabled in.
+///
+/// Servers the profile doesn't list are left as they are when it's applied.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    /// Server name to the clients it's enabled in; an empty list disables it everywhere
+    #[serde(default)]
+    pub enabled: BTreeMap<String, Vec<ConfigType>>,
+}
+
+fn read_configs(manager: &ConfigManager) -> Result<Vec<MCPConfig>> {
+    ConfigType::all().into_iter().map(|ct| manager.read_config(ct)).collect()
+}
+
+/// Where every known server is enabled right now.
+pub fn capture(manager: &ConfigManager) -> Result<Profile> {
+    let enabled = manager
+        .get_managed_servers()?
+        .into_iter()
+        .map(|server| {
+            let clients = ConfigType::all()
+                .into_iter()
+                .filter(|ct| server.systems.get(ct.system_key()).is_some_and(|s| s.enabled))
+                .collect();
+            (server.name, clients)
+        })
+        .collect();
+    Ok(Profile { enabled })
+}
+
+/// Whether the clients are set up exactly as `profile` says.
+pub fn matches(configs: &[MCPConfig], profile: &Profile) -> bool {
+    profile.enabled.iter().all(|(name, clients)| {
+        configs
+            .iter()
+            .all(|config| config.servers.contains_key(name) == clients.contains(&config.config_type))
+    })
+}
+
+/// The first saved profile, by name, that matches the current state.
+pub fn active(manager: &ConfigManager) -> Result<Option<String>> {
+    let configs = read_configs(manager)?;
+    let profiles: BTreeMap<&String, &Profile> = manager.get_state().profiles.iter().collect();
+    Ok(profiles
+        .into_iter()
+        .find(|(_, profile)| matches(&configs, profile))
+        .map(|(name, _)| name.clone()))
+}
+
+/// Plan the enables and disables that switch to a saved profile, with the
+/// same behaviour as `set_server_enabled`: a client's disabled copy comes back
+/// as it was, and clients that never had the server get its canonical definition.
+pub fn plan(manager: &ConfigManager, name: &str) -> Result<ChangePlan> {
+    let profile = manager
+        .get_state()
+        .profiles
+        .get(name)
+        .ok_or_else(|| AppError::InvalidArgument(format!("No profile named {}", name)))?;
+    let configs = read_configs(manager)?;
+    let mut registry = Registry::open()?;
+    registry.adopt(&configs);
+    let reason = format!("Profile {}", name);
+
+    let mut changes = Vec::new();
+    for config in configs {
+        for (server, clients) in &profile.enabled {
+            let wanted = clients.contains(&config.config_type);
+            let (action, before, after) = match (config.servers.get(server), wanted) {
+                (Some(_), true) | (None, false) => continue,
+                (Some(copy), false) => (PlanAction::Disable, Some(copy.clone()), None),
+                (None, true) => {
+                    // A server removed since the profile was saved has nothing to enable
+                    let Some(definition) = config.disabled.get(server).or_else(|| registry.get(server)) else {
+                        continue;
+                    };
+                    (PlanAction::Enable, None, Some(definition.clone()))
+                }
+            };
+            changes.push(PlannedChange {
+                server: server.clone(),
+                config_type: config.config_type,
+                action,
+                before,
+                after,
+                fields: Vec::new(),
+                reason: reason.clone(),
+            });
+        }
+    }
+
+    Ok(ChangePlan {
+        changes,
+        conflicts: Vec::new(),
+    })
+}
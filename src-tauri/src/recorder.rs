@@ -0,0 +1,434 @@
+use crate::config::{ConfigManager, ConfigType, MCPConfig, MCPServer};
+use crate::error::{AppError, Result};
+use crate::secrets;
+use crate::supervisor::resolve_command;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+/// First argument of a client config entry that runs through the recording shim
+pub const RECORD_SUBCOMMAND: &str = "record";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MessageKind {
+    Request,
+    Response,
+    Notification,
+    /// A line that isn't valid JSON-RPC, kept verbatim
+    Invalid,
+}
+
+/// First line of every session file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHeader {
+    pub server: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedMessage {
+    pub timestamp: DateTime<Utc>,
+    pub direction: Direction,
+    pub kind: MessageKind,
+    pub method: Option<String>,
+    pub id: Option<Value>,
+    pub message: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSession {
+    pub id: String,
+    pub server: String,
+    pub started_at: DateTime<Utc>,
+    pub last_message_at: Option<DateTime<Utc>>,
+    pub message_count: usize,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageFilter {
+    pub direction: Option<Direction>,
+    pub kind: Option<MessageKind>,
+    /// Substring match on the JSON-RPC method
+    pub method: Option<String>,
+    /// Substring match anywhere in the raw message
+    pub text: Option<String>,
+}
+
+impl MessageFilter {
+    fn matches(&self, message: &RecordedMessage) -> bool {
+        if self.direction.is_some_and(|d| d != message.direction) {
+            return false;
+        }
+        if self.kind.is_some_and(|k| k != message.kind) {
+            return false;
+        }
+        if let Some(ref method) = self.method {
+            if !message.method.as_deref().is_some_and(|m| m.contains(method.as_str())) {
+                return false;
+            }
+        }
+        if let Some(ref text) = self.text {
+            if !message.message.to_string().contains(text.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub fn recordings_dir() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+        .join(".mcphub")
+        .join("recordings"))
+}
+
+fn classify(line: &str, direction: Direction) -> RecordedMessage {
+    let parsed = serde_json::from_str::<Value>(line).ok().filter(Value::is_object);
+    let (kind, method, id, message) = match parsed {
+        Some(value) => {
+            let method = value.get("method").and_then(Value::as_str).map(String::from);
+            let id = value.get("id").cloned().filter(|id| !id.is_null());
+            let kind = match (&method, &id) {
+                (Some(_), Some(_)) => MessageKind::Request,
+                (Some(_), None) => MessageKind::Notification,
+                (None, Some(_)) => MessageKind::Response,
+                (None, None) => MessageKind::Invalid,
+            };
+            (kind, method, id, value)
+        }
+        None => (MessageKind::Invalid, None, None, Value::String(line.to_string())),
+    };
+
+    RecordedMessage {
+        timestamp: Utc::now(),
+        direction,
+        kind,
+        method,
+        id,
+        message,
+    }
+}
+
+/// Copy lines from `input` to `output`, logging each one to the session file.
+fn pump(
+    input: impl BufRead,
+    mut output: impl Write,
+    direction: Direction,
+    log: Arc<Mutex<BufWriter<File>>>,
+) {
+    for line in input.lines() {
+        let Ok(line) = line else { break };
+
+        if writeln!(output, "{}", line).and_then(|_| output.flush()).is_err() {
+            break;
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let (Ok(mut log), Ok(entry)) = (log.lock(), serde_json::to_string(&classify(&line, direction))) {
+            writeln!(log, "{}", entry).and_then(|_| log.flush()).ok();
+        }
+    }
+}
+
+/// Entry point for `mcphub record --server <name> -- <command> [args...]`.
+///
+/// Runs the real server with stdio passed through unchanged and returns its
+/// exit code.
+pub fn run(args: &[String]) -> Result<i32> {
+    let split = args
+        .iter()
+        .position(|a| a == "--")
+        .ok_or_else(|| AppError::ProcessError("Usage: mcphub record --server <name> -- <command> [args...]".to_string()))?;
+    let server = args[..split]
+        .iter()
+        .position(|a| a == "--server")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+        .unwrap_or_else(|| "unknown".to_string());
+    let (command, command_args) = args[split + 1..]
+        .split_first()
+        .ok_or_else(|| AppError::ProcessError("No server command given".to_string()))?;
+
+    let dir = recordings_dir()?;
+    fs::create_dir_all(&dir)?;
+    let started_at = Utc::now();
+    // The name comes from the command line, so keep it to one plain path
+    // component; dots go too, since session ids may not contain `..`
+    let file_name: String = server
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_".contains(c) { c } else { '-' })
+        .collect();
+    let session_path = dir.join(format!(
+        "{}_{}_{}.jsonl",
+        file_name,
+        started_at.format("%Y%m%d_%H%M%S"),
+        std::process::id()
+    ));
+
+    let header = SessionHeader {
+        server,
+        command: command.clone(),
+        args: command_args.to_vec(),
+        started_at,
+    };
+    // Sessions hold whatever the server was sent, tokens included
+    secrets::write_private(&session_path, format!("{}\n", serde_json::to_string(&header)?).as_bytes())?;
+    let log = BufWriter::new(OpenOptions::new().append(true).open(&session_path)?);
+    let log = Arc::new(Mutex::new(log));
+
+    let program = resolve_command(&ConfigManager::expand_path(command)).unwrap_or_else(|| command.clone());
+    let mut child = Command::new(program)
+        .args(command_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| AppError::ProcessError(e.to_string()))?;
+
+    let child_stdin = child.stdin.take().ok_or_else(|| AppError::ProcessError("No stdin".to_string()))?;
+    let child_stdout = child.stdout.take().ok_or_else(|| AppError::ProcessError("No stdout".to_string()))?;
+
+    // Client -> server; dropping the child's stdin at EOF lets it shut down
+    let inbound_log = Arc::clone(&log);
+    std::thread::spawn(move || {
+        pump(std::io::stdin().lock(), child_stdin, Direction::ClientToServer, inbound_log);
+    });
+
+    pump(BufReader::new(child_stdout), std::io::stdout(), Direction::ServerToClient, log);
+
+    let status = child.wait()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Whether a client config entry currently launches through the recording
+/// shim, going by its `record --server <name> -- <command>` args. The command
+/// isn't compared with this executable, since the app may have moved since.
+pub fn is_proxied(server: &MCPServer) -> bool {
+    matches!(
+        server.args.as_slice(),
+        [record, flag, _, separator, _, ..] if record == RECORD_SUBCOMMAND && flag == "--server" && separator == "--"
+    )
+}
+
+/// Rewrite a server's entry in `config_type` so it launches through the shim.
+pub fn enable(manager: &ConfigManager, name: &str, config_type: ConfigType) -> Result<()> {
+    let mut config = manager.read_config(config_type)?;
+    let server = config
+        .servers
+        .get_mut(name)
+        .ok_or_else(|| AppError::ServerNotFound(name.to_string()))?;
+
+    let exe = std::env::current_exe()?;
+    if is_proxied(server) {
+        // Follow the app if it moved since the shim was set up
+        if server.command != exe.to_string_lossy() {
+            server.command = exe.to_string_lossy().to_string();
+            manager.write_config(config_type, &config.servers)?;
+        }
+        return Ok(());
+    }
+
+    *server = proxy(exe.to_string_lossy().to_string(), name, server.clone());
+    manager.write_config(config_type, &config.servers)
+}
+
+fn proxy(exe: String, name: &str, mut server: MCPServer) -> MCPServer {
+    let mut args = vec![
+        RECORD_SUBCOMMAND.to_string(),
+        "--server".to_string(),
+        name.to_string(),
+        "--".to_string(),
+        server.command,
+    ];
+    args.append(&mut server.args);
+    MCPServer {
+        command: exe,
+        args,
+        ..server
+    }
+}
+
+/// The entry a server would have without the shim, so copies compare by what
+/// they launch and canonical definitions never hold the shim. Entries that
+/// don't run through it are returned as they are.
+pub fn unproxied(server: &MCPServer) -> MCPServer {
+    let mut server = server.clone();
+    if is_proxied(&server) {
+        // Everything after the first `--` is the original command line
+        let split = server.args.iter().position(|a| a == "--").unwrap_or_default();
+        let mut original = server.args.split_off(split + 1).into_iter();
+        server.command = original.next().unwrap_or_default();
+        server.args = original.collect();
+    }
+    server
+}
+
+/// A client config with every entry [`unproxied`], for comparing copies.
+pub fn unproxied_config(mut config: MCPConfig) -> MCPConfig {
+    for server in config.servers.values_mut().chain(config.disabled.values_mut()) {
+        *server = unproxied(server);
+    }
+    config
+}
+
+/// `server` launched through the same shim as `current` if that runs through
+/// one, so a new definition written over a recorded entry stays recorded.
+pub fn proxied_like(current: Option<&MCPServer>, server: MCPServer) -> MCPServer {
+    match current {
+        Some(current) if is_proxied(current) && !is_proxied(&server) => {
+            proxy(current.command.clone(), &current.args[2], server)
+        }
+        _ => server,
+    }
+}
+
+/// Point a renamed server's shim at its new name, so later sessions are
+/// recorded under it. Entries that don't run through the shim are left alone.
+pub fn rename_proxied(server: &mut MCPServer, name: &str) {
+    if !is_proxied(server) {
+        return;
+    }
+    let split = server.args.iter().position(|a| a == "--").unwrap_or_default();
+    let flag = server.args[..split].iter().position(|a| a == "--server");
+    if let Some(i) = flag.filter(|i| i + 1 < split) {
+        server.args[i + 1] = name.to_string();
+    }
+}
+
+/// Relabel the recorded sessions of a renamed server. Session ids stay the same.
+pub fn rename_sessions(old_name: &str, new_name: &str) -> Result<()> {
+    let dir = recordings_dir()?;
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let (first, rest) = content.split_once('\n').unwrap_or((&content, ""));
+        let Ok(mut header) = serde_json::from_str::<SessionHeader>(first) else {
+            continue;
+        };
+        if header.server != old_name {
+            continue;
+        }
+        header.server = new_name.to_string();
+        fs::write(&path, format!("{}\n{}", serde_json::to_string(&header)?, rest))?;
+    }
+    Ok(())
+}
+
+/// Restore the original entry for a server that runs through the shim.
+pub fn disable(manager: &ConfigManager, name: &str, config_type: ConfigType) -> Result<()> {
+    let mut config = manager.read_config(config_type)?;
+    let server = config
+        .servers
+        .get_mut(name)
+        .ok_or_else(|| AppError::ServerNotFound(name.to_string()))?;
+
+    if !is_proxied(server) {
+        return Ok(());
+    }
+    *server = unproxied(server);
+    manager.write_config(config_type, &config.servers)
+}
+
+fn session_path(id: &str) -> Result<PathBuf> {
+    if id.contains('/') || id.contains('\\') || id.contains("..") {
+        return Err(AppError::PathError(format!("Invalid recording id: {}", id)));
+    }
+    Ok(recordings_dir()?.join(format!("{}.jsonl", id)))
+}
+
+/// List recorded sessions, newest first, optionally for one server.
+pub fn list_sessions(server: Option<&str>) -> Result<Vec<RecordingSession>> {
+    let dir = recordings_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions = Vec::new();
+    for entry in fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+
+        let mut lines = BufReader::new(File::open(&path)?).lines();
+        let Some(header) = lines
+            .next()
+            .and_then(|l| l.ok())
+            .and_then(|l| serde_json::from_str::<SessionHeader>(&l).ok())
+        else {
+            continue;
+        };
+        if server.is_some_and(|s| s != header.server) {
+            continue;
+        }
+
+        let mut message_count = 0;
+        let mut last_line = None;
+        for line in lines.map_while(|l| l.ok()) {
+            message_count += 1;
+            last_line = Some(line);
+        }
+        let last_message_at = last_line
+            .and_then(|l| serde_json::from_str::<RecordedMessage>(&l).ok())
+            .map(|m| m.timestamp);
+
+        sessions.push(RecordingSession {
+            id: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            server: header.server,
+            started_at: header.started_at,
+            last_message_at,
+            message_count,
+            path: path.to_string_lossy().to_string(),
+        });
+    }
+
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.started_at));
+    Ok(sessions)
+}
+
+/// Read the messages of one session that match `filter`.
+pub fn read_session(id: &str, filter: &MessageFilter) -> Result<Vec<RecordedMessage>> {
+    let path = session_path(id)?;
+    if !path.exists() {
+        return Err(AppError::ConfigNotFound(path.to_string_lossy().to_string()));
+    }
+
+    Ok(BufReader::new(File::open(&path)?)
+        .lines()
+        .skip(1)
+        .map_while(|l| l.ok())
+        .filter_map(|l| serde_json::from_str::<RecordedMessage>(&l).ok())
+        .filter(|m| filter.matches(m))
+        .collect())
+}
@@ -0,0 +1,264 @@
+use crate::config::MCPServer;
+use crate::error::{AppError, Result};
+use crate::secrets::secret_name;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Prefix of values that stand in for a redacted secret in exported JSON
+pub const PLACEHOLDER_SCHEME: &str = "mcphub-placeholder://";
+
+/// Well-known token prefixes (GitHub, OpenAI/Anthropic, Stripe, Slack, AWS, Google, GitLab, Perplexity, Hugging Face)
+const TOKEN_PREFIXES: &[&str] = &[
+    "ghp_", "gho_", "ghu_", "ghs_", "ghr_", "github_pat_", "sk-", "sk_live_", "sk_test_", "rk_live_",
+    "xoxb-", "xoxp-", "xoxa-", "xoxs-", "AKIA", "AIza", "glpat-", "pplx-", "hf_",
+];
+
+/// Substrings of env/header names that mark their value as secret
+const SECRET_KEY_MARKERS: &[&str] = &["PASSWORD", "PASSWD", "SECRET", "CREDENTIAL", "AUTHORIZATION", "COOKIE", "API_KEY", "APIKEY"];
+
+/// Suffixes of env/header names that mark their value as secret
+const SECRET_KEY_SUFFIXES: &[&str] = &["_KEY", "_TOKEN", "_PAT"];
+
+/// Authorization schemes kept in front of a redacted credential
+const AUTH_SCHEMES: &[&str] = &["Bearer", "Basic", "Token", "Bot"];
+
+const MIN_ENTROPY_LENGTH: usize = 20;
+const MIN_ENTROPY_BITS: f64 = 3.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Detection {
+    KeyName,
+    TokenPrefix,
+    Entropy,
+    /// A reference into the local vault, which means nothing on another machine
+    VaultReference,
+}
+
+/// One value that was replaced by a placeholder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Redaction {
+    pub placeholder: String,
+    pub server: String,
+    /// Where the value lived, e.g. `env.GITHUB_TOKEN`, `headers.Authorization` or `args[3]`
+    pub location: String,
+    pub detection: Detection,
+}
+
+/// Placeholder name referenced by `value`, if it is one.
+pub fn placeholder_name(value: &str) -> Option<&str> {
+    value.strip_prefix(PLACEHOLDER_SCHEME).filter(|name| !name.is_empty())
+}
+
+fn normalize_key(key: &str) -> String {
+    key.trim_start_matches('-').to_uppercase().replace('-', "_")
+}
+
+/// Split `Bearer <token>` and the like into the scheme with its space, and the credential.
+fn split_scheme(value: &str) -> (&str, &str) {
+    let scheme = AUTH_SCHEMES.iter().find(|scheme| {
+        value.len() > scheme.len()
+            && value[..scheme.len()].eq_ignore_ascii_case(scheme)
+            && value[scheme.len()..].starts_with(' ')
+    });
+    match scheme {
+        Some(scheme) => value.split_at(scheme.len() + 1),
+        None => ("", value),
+    }
+}
+
+fn shannon_entropy(value: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in value.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let len = value.chars().count() as f64;
+    counts
+        .values()
+        .map(|&n| {
+            let p = n as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Decide whether `value` (optionally stored under `key`) looks like a secret.
+pub fn detect(key: Option<&str>, value: &str) -> Option<Detection> {
+    let value = value.trim();
+    if secret_name(value).is_some() {
+        return Some(Detection::VaultReference);
+    }
+    if value.is_empty() || placeholder_name(value).is_some() {
+        return None;
+    }
+    // Interpolated variables are resolved by the client, not secrets themselves
+    if value.starts_with("${") && value.ends_with('}') {
+        return None;
+    }
+
+    if let Some(key) = key.map(normalize_key) {
+        if SECRET_KEY_SUFFIXES.iter().any(|s| key.ends_with(s))
+            || SECRET_KEY_MARKERS.iter().any(|m| key.contains(m))
+            || key == "TOKEN"
+            || key == "KEY"
+        {
+            return Some(Detection::KeyName);
+        }
+    }
+
+    let bare = split_scheme(value).1;
+    if TOKEN_PREFIXES.iter().any(|p| bare.starts_with(p) && bare.len() > p.len() + 8) {
+        return Some(Detection::TokenPrefix);
+    }
+
+    let looks_opaque = bare.len() >= MIN_ENTROPY_LENGTH
+        && !bare.contains(char::is_whitespace)
+        && !bare.contains("://")
+        && !bare.starts_with('/')
+        && !bare.starts_with('~')
+        && !bare.starts_with('@')
+        && bare.chars().any(|c| c.is_ascii_digit())
+        && bare.chars().any(|c| c.is_ascii_alphabetic());
+    if looks_opaque && shannon_entropy(bare) >= MIN_ENTROPY_BITS {
+        return Some(Detection::Entropy);
+    }
+
+    None
+}
+
+/// Hands out placeholder names, reusing one name for a repeated value.
+#[derive(Default)]
+struct PlaceholderNames {
+    by_value: HashMap<String, String>,
+    taken: BTreeSet<String>,
+}
+
+impl PlaceholderNames {
+    fn name_for(&mut self, server: &str, key: &str, value: &str) -> String {
+        if let Some(name) = self.by_value.get(value) {
+            return name.clone();
+        }
+
+        let mut name = normalize_key(key);
+        if self.taken.contains(&name) {
+            name = format!("{}_{}", normalize_key(server), name);
+        }
+        let base = name.clone();
+        let mut n = 2;
+        while self.taken.contains(&name) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+
+        self.taken.insert(name.clone());
+        self.by_value.insert(value.to_string(), name.clone());
+        name
+    }
+}
+
+/// Replace every likely secret in `servers` with a named placeholder. An
+/// authorization scheme such as `Bearer ` stays in front of it, so filling the
+/// placeholder in on import gives back a working header.
+pub fn redact_servers(servers: &HashMap<String, MCPServer>) -> (HashMap<String, MCPServer>, Vec<Redaction>) {
+    let mut names = PlaceholderNames::default();
+    let mut redactions = Vec::new();
+    let mut redacted = servers.clone();
+
+    let mut server_names: Vec<String> = servers.keys().cloned().collect();
+    server_names.sort();
+
+    for server_name in server_names {
+        let Some(server) = redacted.get_mut(&server_name) else { continue };
+        // `key` feeds detection; `hint` names the placeholder when there is no key
+        let mut redact = |key: Option<&str>, hint: &str, location: String, value: &mut String| {
+            if let Some(detection) = detect(key, value) {
+                let (scheme, credential) = split_scheme(value);
+                let placeholder = names.name_for(&server_name, key.unwrap_or(hint), credential);
+                *value = format!("{}{}{}", scheme, PLACEHOLDER_SCHEME, placeholder);
+                redactions.push(Redaction {
+                    placeholder,
+                    server: server_name.clone(),
+                    location,
+                    detection,
+                });
+            }
+        };
+
+        let mut env_keys: Vec<String> = server.env.keys().cloned().collect();
+        env_keys.sort();
+        for key in env_keys {
+            if let Some(value) = server.env.get_mut(&key) {
+                redact(Some(&key), &key, format!("env.{}", key), value);
+            }
+        }
+
+        let mut header_keys: Vec<String> = server.headers.keys().cloned().collect();
+        header_keys.sort();
+        for key in header_keys {
+            if let Some(value) = server.headers.get_mut(&key) {
+                redact(Some(&key), &key, format!("headers.{}", key), value);
+            }
+        }
+
+        // Args carry secrets as `--flag=value` or `--flag value`
+        for i in 0..server.args.len() {
+            let arg = server.args[i].clone();
+            if let Some((flag, value)) = arg.split_once('=').filter(|(f, _)| f.starts_with('-')) {
+                let mut value = value.to_string();
+                redact(Some(flag), flag, format!("args[{}]", i), &mut value);
+                server.args[i] = format!("{}={}", flag, value);
+            } else if i > 0 && server.args[i - 1].starts_with("--") && !server.args[i - 1].contains('=') {
+                let flag = server.args[i - 1].clone();
+                redact(Some(&flag), &flag, format!("args[{}]", i), &mut server.args[i]);
+            } else {
+                let hint = format!("{}_arg_{}", server_name, i);
+                redact(None, &hint, format!("args[{}]", i), &mut server.args[i]);
+            }
+        }
+    }
+
+    (redacted, redactions)
+}
+
+fn server_values(server: &mut MCPServer) -> impl Iterator<Item = &mut String> {
+    server
+        .env
+        .values_mut()
+        .chain(server.headers.values_mut())
+        .chain(server.args.iter_mut())
+}
+
+/// Every placeholder name referenced by `servers`, sorted.
+pub fn find_placeholders(servers: &HashMap<String, MCPServer>) -> Vec<String> {
+    let mut found = BTreeSet::new();
+    for server in servers.values() {
+        for value in server.env.values().chain(server.headers.values()).chain(server.args.iter()) {
+            if let Some(name) = value.split_once(PLACEHOLDER_SCHEME).map(|(_, name)| name) {
+                found.insert(name.to_string());
+            }
+        }
+    }
+    found.into_iter().collect()
+}
+
+/// Substitute user-provided values for placeholders, failing if any are missing.
+pub fn fill_placeholders(servers: &mut HashMap<String, MCPServer>, values: &HashMap<String, String>) -> Result<()> {
+    let missing: Vec<String> = find_placeholders(servers)
+        .into_iter()
+        .filter(|name| !values.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::MissingPlaceholders(missing.join(", ")));
+    }
+
+    for server in servers.values_mut() {
+        for value in server_values(server) {
+            if let Some((prefix, name)) = value.split_once(PLACEHOLDER_SCHEME) {
+                *value = format!("{}{}", prefix, values[name]);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use crate::config::{AppState, ConfigManager, MCPConfig, MCPServer};
+use crate::error::{AppError, Result};
+use crate::recorder;
+use crate::sync::{keep_pinned, Agreement};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct RegistryFile {
+    #[serde(default)]
+    servers: HashMap<String, MCPServer>,
+}
+
+/// mcphub's own canonical definition of every server, in `~/.mcphub/registry.json`.
+///
+/// Client configs are projections of these definitions plus the client's
+/// pinned fields. A client whose copy differs otherwise has drifted. Secret
+/// values are stored as vault references.
+pub struct Registry {
+    path: PathBuf,
+    file: RegistryFile,
+}
+
+impl Registry {
+    pub fn open() -> Result<Self> {
+        let dir = dirs::home_dir()
+            .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+            .join(".mcphub");
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("registry.json");
+        let file = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?).unwrap_or_default()
+        } else {
+            RegistryFile::default()
+        };
+        Ok(Registry { path, file })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.file)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MCPServer> {
+        self.file.servers.get(name)
+    }
+
+    /// Make these the canonical definitions.
+    pub fn set(&mut self, servers: &HashMap<String, MCPServer>) -> Result<()> {
+        if servers.is_empty() {
+            return Ok(());
+        }
+        self.file.servers.extend(ConfigManager::reference_secrets(servers));
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        if self.file.servers.remove(name).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn rename(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        if let Some(server) = self.file.servers.remove(old_name) {
+            self.file.servers.insert(new_name.to_string(), server);
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Treat servers found in client configs that mcphub doesn't know yet as
+    /// canonical, taking the first enabled copy in client order, else the first
+    /// disabled one. Only in memory, so reads can use it; returns whether any
+    /// were new, for writes to [`save`](Self::save) them.
+    pub fn adopt(&mut self, configs: &[MCPConfig]) -> bool {
+        let mut found = HashMap::new();
+        for servers in configs.iter().map(|c| &c.servers).chain(configs.iter().map(|c| &c.disabled)) {
+            for (name, server) in servers {
+                if !self.file.servers.contains_key(name) && !found.contains_key(name) {
+                    found.insert(name.clone(), recorder::unproxied(server));
+                }
+            }
+        }
+        let adopted = !found.is_empty();
+        self.file.servers.extend(ConfigManager::reference_secrets(&found));
+        adopted
+    }
+
+    /// Take the definitions every client agrees on after a sync, leaving out the
+    /// values pinned in the client each came from, and drop servers gone from all.
+    pub fn record(&mut self, agreement: &Agreement, state: &AppState) -> Result<()> {
+        for (name, (config_type, copy)) in &agreement.servers {
+            let server = keep_pinned(copy, self.file.servers.get(name), state.pinned(name, *config_type));
+            self.file.servers.insert(name.clone(), server);
+        }
+        self.file.servers.retain(|name, _| agreement.present.contains(name));
+        self.save()
+    }
+}
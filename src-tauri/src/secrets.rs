@@ -0,0 +1,288 @@
+use crate::error::{AppError, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Prefix of env values that refer to a vault secret instead of holding it
+pub const SECRET_SCHEME: &str = "mcphub-secret://";
+
+/// The secret name referenced by `value`, if it is a secret reference.
+pub fn secret_name(value: &str) -> Option<&str> {
+    value.strip_prefix(SECRET_SCHEME).filter(|name| !name.is_empty())
+}
+
+pub fn secret_ref(name: &str) -> String {
+    format!("{}{}", SECRET_SCHEME, name)
+}
+
+/// Whether any env value of a server refers to the vault.
+pub fn has_secret_refs(env: &HashMap<String, String>) -> bool {
+    env.values().any(|v| secret_name(v).is_some())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretInfo {
+    pub name: String,
+    pub updated_at: DateTime<Utc>,
+    /// `server.ENV_KEY` pairs whose client config values came from this secret
+    pub used_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptedSecret {
+    nonce: String,
+    ciphertext: String,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct VaultFile {
+    #[serde(default)]
+    secrets: HashMap<String, EncryptedSecret>,
+    /// server name -> env key -> secret name, recorded whenever a reference is
+    /// resolved into a client config so the literal can be shown as a reference again
+    #[serde(default)]
+    bindings: HashMap<String, HashMap<String, String>>,
+}
+
+/// Create a file only its owner can read, failing if it already exists. The
+/// permissions are set as the file is created, so it's never readable by others.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)?;
+    Ok(())
+}
+
+/// Encrypted store for API keys and tokens under `~/.mcphub`.
+///
+/// Values are sealed with ChaCha20-Poly1305 using a key kept in
+/// `~/.mcphub/vault.key` with owner-only permissions. This keeps tokens out of
+/// mcphub's own files and exports; clients that need literals still receive them.
+pub struct SecretVault {
+    path: PathBuf,
+    key_path: PathBuf,
+    file: VaultFile,
+}
+
+impl SecretVault {
+    pub fn open() -> Result<Self> {
+        let dir = dirs::home_dir()
+            .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+            .join(".mcphub");
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("vault.json");
+        let file = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            VaultFile::default()
+        };
+
+        Ok(SecretVault {
+            path,
+            key_path: dir.join("vault.key"),
+            file,
+        })
+    }
+
+    fn cipher(&self) -> Result<ChaCha20Poly1305> {
+        if !self.key_path.exists() {
+            // A new key can't open what the lost one sealed
+            if !self.file.secrets.is_empty() {
+                return Err(AppError::SecretError(format!(
+                    "{} is missing, so the stored secrets can't be decrypted",
+                    self.key_path.display()
+                )));
+            }
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+            write_private(&self.key_path, key.as_slice())?;
+        }
+
+        let key = fs::read(&self.key_path)?;
+        if key.len() != 32 {
+            return Err(AppError::SecretError("Vault key is corrupt".to_string()));
+        }
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.file)?;
+        fs::write(&self.path, json)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<SecretInfo> {
+        let mut secrets: Vec<SecretInfo> = self
+            .file
+            .secrets
+            .iter()
+            .map(|(name, secret)| {
+                let mut used_by: Vec<String> = self
+                    .file
+                    .bindings
+                    .iter()
+                    .flat_map(|(server, keys)| {
+                        keys.iter()
+                            .filter(|(_, s)| *s == name)
+                            .map(move |(key, _)| format!("{}.{}", server, key))
+                    })
+                    .collect();
+                used_by.sort();
+                SecretInfo {
+                    name: name.clone(),
+                    updated_at: secret.updated_at,
+                    used_by,
+                }
+            })
+            .collect();
+        secrets.sort_by(|a, b| a.name.cmp(&b.name));
+        secrets
+    }
+
+    pub fn get(&self, name: &str) -> Result<String> {
+        let secret = self
+            .file
+            .secrets
+            .get(name)
+            .ok_or_else(|| AppError::SecretNotFound(name.to_string()))?;
+
+        let nonce = BASE64
+            .decode(&secret.nonce)
+            .map_err(|e| AppError::SecretError(e.to_string()))?;
+        let ciphertext = BASE64
+            .decode(&secret.ciphertext)
+            .map_err(|e| AppError::SecretError(e.to_string()))?;
+        let plaintext = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| AppError::SecretError(format!("Could not decrypt secret: {}", name)))?;
+
+        String::from_utf8(plaintext).map_err(|e| AppError::SecretError(e.to_string()))
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) {
+            return Err(AppError::SecretError(format!(
+                "Secret names may only contain letters, digits, '-', '_' and '.': {}",
+                name
+            )));
+        }
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(&nonce, value.as_bytes())
+            .map_err(|e| AppError::SecretError(e.to_string()))?;
+
+        self.file.secrets.insert(
+            name.to_string(),
+            EncryptedSecret {
+                nonce: BASE64.encode(nonce),
+                ciphertext: BASE64.encode(ciphertext),
+                updated_at: Utc::now(),
+            },
+        );
+        self.save()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<()> {
+        if self.file.secrets.remove(name).is_none() {
+            return Err(AppError::SecretNotFound(name.to_string()));
+        }
+        for keys in self.file.bindings.values_mut() {
+            keys.retain(|_, s| s != name);
+        }
+        self.file.bindings.retain(|_, keys| !keys.is_empty());
+        self.save()
+    }
+
+    /// Move a server's bindings to its new name.
+    pub fn rename_server(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        match self.file.bindings.remove(old_name) {
+            Some(keys) => {
+                self.file.bindings.insert(new_name.to_string(), keys);
+                self.save()
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Replace every secret reference in `env` with its value, remembering
+    /// which keys of `server` were bound to which secret.
+    pub fn resolve_env(&mut self, server: &str, env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+        let mut resolved = env.clone();
+        let mut bound = Vec::new();
+
+        for (key, value) in resolved.iter_mut() {
+            if let Some(name) = secret_name(value).map(String::from) {
+                *value = self.get(&name)?;
+                bound.push((key.clone(), name));
+            }
+        }
+
+        if !bound.is_empty() {
+            let bindings = self.file.bindings.entry(server.to_string()).or_default();
+            let changed = bound
+                .iter()
+                .any(|(key, name)| bindings.get(key) != Some(name));
+            if changed {
+                bindings.extend(bound);
+                self.save()?;
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// The inverse of [`resolve_env`](Self::resolve_env): swap literals that
+    /// still match their bound secret back to `mcphub-secret://` references.
+    pub fn reference_env(&self, server: &str, env: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut referenced = env.clone();
+        let Some(bindings) = self.file.bindings.get(server) else {
+            return referenced;
+        };
+
+        for (key, value) in referenced.iter_mut() {
+            if let Some(name) = bindings.get(key) {
+                if self.get(name).is_ok_and(|secret| secret == *value) {
+                    *value = secret_ref(name);
+                }
+            }
+        }
+
+        referenced
+    }
+}
+
+/// Resolve secret references in a server's env for spawning it directly.
+pub fn resolve_for_spawn(env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+    if !has_secret_refs(env) {
+        return Ok(env.clone());
+    }
+
+    let vault = SecretVault::open()?;
+    env.iter()
+        .map(|(key, value)| {
+            let value = match secret_name(value) {
+                Some(name) => vault.get(name)?,
+                None => value.clone(),
+            };
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
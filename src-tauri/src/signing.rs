@@ -0,0 +1,347 @@
+use crate::bundle::Bundle;
+use crate::error::{AppError, Result};
+use crate::secrets::write_private;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::OsRng;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// What to do with a bundle that isn't signed by a trusted key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BundlePolicy {
+    /// Import anyway and report the problem
+    #[default]
+    Warn,
+    /// Refuse to import
+    Block,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleSignature {
+    pub signer: String,
+    /// Base64 ed25519 public key
+    pub public_key: String,
+    /// Base64 signature over the bundle's canonical JSON without this field
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureStatus {
+    Unsigned,
+    /// The signature doesn't match the contents
+    Invalid,
+    /// Signed by a key in the trust store
+    Trusted,
+    /// Validly signed by a key mcphub hasn't seen before
+    Untrusted,
+    /// Signed by a known signer with a different key than the one trusted
+    KeyChanged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Verification {
+    pub status: SignatureStatus,
+    pub signer: Option<String>,
+    pub fingerprint: Option<String>,
+    pub message: String,
+}
+
+/// The local signing key's public half.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningIdentity {
+    pub name: String,
+    pub public_key: String,
+    pub fingerprint: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyFile {
+    name: String,
+    secret_key: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustedKey {
+    pub name: String,
+    pub public_key: String,
+    pub fingerprint: String,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct TrustFile {
+    #[serde(default)]
+    keys: Vec<TrustedKey>,
+}
+
+fn mcphub_dir() -> Result<PathBuf> {
+    let dir = dirs::home_dir()
+        .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+        .join(".mcphub");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Short, readable form of a public key, e.g. `3f9a 02c1 7be4 d810`.
+pub fn fingerprint(public_key: &str) -> String {
+    let bytes = BASE64.decode(public_key).unwrap_or_default();
+    bytes
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .chunks(2)
+        .map(|pair| pair.concat())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_key(public_key: &str) -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = BASE64.decode(public_key).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+/// The bytes that get signed: the bundle as JSON with sorted keys and no signature.
+fn payload(bundle: &Bundle) -> Result<Vec<u8>> {
+    let mut value = serde_json::to_value(bundle)?;
+    if let Some(object) = value.as_object_mut() {
+        object.remove("signature");
+    }
+    Ok(serde_json::to_vec(&value)?)
+}
+
+fn key_path() -> Result<PathBuf> {
+    Ok(mcphub_dir()?.join("signing.key"))
+}
+
+fn read_key() -> Result<Option<(KeyFile, SigningKey)>> {
+    let path = key_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let file: KeyFile = serde_json::from_str(&fs::read_to_string(&path)?)?;
+    let bytes: [u8; 32] = BASE64
+        .decode(&file.secret_key)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| AppError::SigningError("Signing key is corrupt".to_string()))?;
+    Ok(Some((file, SigningKey::from_bytes(&bytes))))
+}
+
+fn identity_of(file: &KeyFile, key: &SigningKey) -> SigningIdentity {
+    let public_key = BASE64.encode(key.verifying_key().as_bytes());
+    SigningIdentity {
+        name: file.name.clone(),
+        fingerprint: fingerprint(&public_key),
+        public_key,
+        created_at: file.created_at,
+    }
+}
+
+pub fn identity() -> Result<Option<SigningIdentity>> {
+    Ok(read_key()?.map(|(file, key)| identity_of(&file, &key)))
+}
+
+/// Create the local signing key, kept in `~/.mcphub/signing.key` with owner-only permissions.
+pub fn generate_key(name: &str, overwrite: bool) -> Result<SigningIdentity> {
+    let path = key_path()?;
+    if path.exists() && !overwrite {
+        return Err(AppError::SigningError("A signing key already exists".to_string()));
+    }
+
+    let key = SigningKey::generate(&mut OsRng);
+    let file = KeyFile {
+        name: name.to_string(),
+        secret_key: BASE64.encode(key.to_bytes()),
+        created_at: Utc::now(),
+    };
+    // Written owner-only from the start, then moved over any old key in one step
+    let new_path = path.with_extension("key.new");
+    if new_path.exists() {
+        fs::remove_file(&new_path)?;
+    }
+    write_private(&new_path, serde_json::to_string_pretty(&file)?.as_bytes())?;
+    fs::rename(&new_path, &path)?;
+
+    // Bundles we sign ourselves are trusted without asking
+    let identity = identity_of(&file, &key);
+    TrustStore::open()?.trust(&identity.name, &identity.public_key)?;
+    Ok(identity)
+}
+
+/// Sign a bundle with the local key, replacing any previous signature.
+pub fn sign(bundle: &mut Bundle) -> Result<()> {
+    let (file, key) = read_key()?
+        .ok_or_else(|| AppError::SigningError("No signing key; generate one first".to_string()))?;
+
+    bundle.signature = None;
+    bundle.author.get_or_insert_with(|| file.name.clone());
+    let signature = key.sign(&payload(bundle)?);
+
+    bundle.signature = Some(BundleSignature {
+        signer: file.name,
+        public_key: BASE64.encode(key.verifying_key().as_bytes()),
+        signature: BASE64.encode(signature.to_bytes()),
+    });
+    Ok(())
+}
+
+/// Check a bundle's signature and whether its key is trusted.
+pub fn verify(bundle: &Bundle, trust: &TrustStore) -> Result<Verification> {
+    let Some(ref sig) = bundle.signature else {
+        return Ok(Verification {
+            status: SignatureStatus::Unsigned,
+            signer: None,
+            fingerprint: None,
+            message: "This bundle is not signed. Its servers run commands on your machine.".to_string(),
+        });
+    };
+
+    let payload = payload(bundle)?;
+    let valid = match (decode_key(&sig.public_key), BASE64.decode(&sig.signature)) {
+        (Some(key), Ok(bytes)) => Signature::from_slice(&bytes)
+            .is_ok_and(|signature| key.verify(&payload, &signature).is_ok()),
+        _ => false,
+    };
+
+    let (status, message) = if !valid {
+        (
+            SignatureStatus::Invalid,
+            "The signature does not match; the bundle was modified after signing".to_string(),
+        )
+    } else if let Some(known) = trust.key(&sig.public_key) {
+        // The signer field isn't covered by the signature, so name the key as we know it
+        (SignatureStatus::Trusted, format!("Signed by {}", known.name))
+    } else if let Some(known) = trust.keys_for(&sig.signer).first() {
+        (
+            SignatureStatus::KeyChanged,
+            format!(
+                "Signed as {} with a different key than the trusted one ({})",
+                sig.signer, known.fingerprint
+            ),
+        )
+    } else {
+        (
+            SignatureStatus::Untrusted,
+            format!("Signed by {}, whose key is not trusted yet", sig.signer),
+        )
+    };
+
+    let signer = trust.key(&sig.public_key).map_or_else(|| sig.signer.clone(), |k| k.name.clone());
+    Ok(Verification {
+        status,
+        signer: Some(signer),
+        fingerprint: Some(fingerprint(&sig.public_key)),
+        message,
+    })
+}
+
+/// Decide whether a bundle may be imported, trusting a first-seen signer if allowed.
+///
+/// Invalid signatures and changed keys always block. Unsigned bundles and new
+/// signers follow `policy`, except that `trust_signer` explicitly accepts a new signer.
+pub fn enforce(
+    bundle: &Bundle,
+    verification: &Verification,
+    policy: BundlePolicy,
+    trust_signer: bool,
+) -> Result<()> {
+    match verification.status {
+        SignatureStatus::Trusted => Ok(()),
+        SignatureStatus::Invalid | SignatureStatus::KeyChanged => {
+            Err(AppError::UntrustedBundle(verification.message.clone()))
+        }
+        SignatureStatus::Unsigned if policy == BundlePolicy::Block => {
+            Err(AppError::UntrustedBundle(verification.message.clone()))
+        }
+        SignatureStatus::Unsigned => Ok(()),
+        SignatureStatus::Untrusted if policy == BundlePolicy::Block && !trust_signer => {
+            Err(AppError::UntrustedBundle(verification.message.clone()))
+        }
+        SignatureStatus::Untrusted => match bundle.signature {
+            // Trust on first use: remember the key so a later change is caught
+            Some(ref sig) => TrustStore::open()?.trust(&sig.signer, &sig.public_key),
+            None => Ok(()),
+        },
+    }
+}
+
+/// Public keys whose bundles import without warnings, in `~/.mcphub/trust.json`.
+pub struct TrustStore {
+    path: PathBuf,
+    file: TrustFile,
+}
+
+impl TrustStore {
+    pub fn open() -> Result<Self> {
+        let path = mcphub_dir()?.join("trust.json");
+        let file = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?)?
+        } else {
+            TrustFile::default()
+        };
+        Ok(TrustStore { path, file })
+    }
+
+    fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.file)?;
+        fs::write(&self.path, json)?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<TrustedKey> {
+        self.file.keys.clone()
+    }
+
+    pub fn is_trusted(&self, public_key: &str) -> bool {
+        self.key(public_key).is_some()
+    }
+
+    fn key(&self, public_key: &str) -> Option<&TrustedKey> {
+        self.file.keys.iter().find(|k| k.public_key == public_key)
+    }
+
+    fn keys_for(&self, name: &str) -> Vec<&TrustedKey> {
+        self.file.keys.iter().filter(|k| k.name == name).collect()
+    }
+
+    pub fn trust(&mut self, name: &str, public_key: &str) -> Result<()> {
+        if decode_key(public_key).is_none() {
+            return Err(AppError::SigningError(format!("Not an ed25519 public key: {}", public_key)));
+        }
+        if self.is_trusted(public_key) {
+            return Ok(());
+        }
+
+        self.file.keys.push(TrustedKey {
+            name: name.to_string(),
+            public_key: public_key.to_string(),
+            fingerprint: fingerprint(public_key),
+            added_at: Utc::now(),
+        });
+        self.save()
+    }
+
+    pub fn remove(&mut self, public_key: &str) -> Result<()> {
+        let before = self.file.keys.len();
+        self.file.keys.retain(|k| k.public_key != public_key);
+        if self.file.keys.len() == before {
+            return Err(AppError::SigningError(format!("Key is not trusted: {}", public_key)));
+        }
+        self.save()
+    }
+}
@@ -0,0 +1,56 @@
+use crate::config::{ConfigType, MCPServer};
+use crate::error::{AppError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct StashFile {
+    #[serde(default)]
+    clients: HashMap<ConfigType, HashMap<String, MCPServer>>,
+}
+
+/// Servers disabled in clients that have no `disabled` flag of their own, kept
+/// in `~/.mcphub/disabled.json` so enabling them again restores the definition.
+///
+/// Secret values are stored as vault references, never in the clear.
+pub struct DisabledStash {
+    path: PathBuf,
+    file: StashFile,
+}
+
+impl DisabledStash {
+    pub fn open() -> Result<Self> {
+        let dir = dirs::home_dir()
+            .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+            .join(".mcphub");
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("disabled.json");
+        let file = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?).unwrap_or_default()
+        } else {
+            StashFile::default()
+        };
+        Ok(DisabledStash { path, file })
+    }
+
+    pub fn servers(&self, config_type: ConfigType) -> HashMap<String, MCPServer> {
+        self.file.clients.get(&config_type).cloned().unwrap_or_default()
+    }
+
+    /// Replace the servers stashed for a client.
+    pub fn set_servers(&mut self, config_type: ConfigType, servers: HashMap<String, MCPServer>) -> Result<()> {
+        if servers.is_empty() {
+            if self.file.clients.remove(&config_type).is_none() {
+                return Ok(());
+            }
+        } else {
+            self.file.clients.insert(config_type, servers);
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.file)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use crate::config::{ConfigManager, MCPServer};
+use crate::error::{AppError, Result};
+use crate::interpolate::render_for_spawn;
+use crate::monitor::{default_sampler, ResourceSample, ResourceSampler};
+use crate::secrets::resolve_for_spawn;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How often running servers are sampled
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Number of samples kept per server (10 minutes at the default interval)
+const MAX_SAMPLES: usize = 300;
+
+/// Resolve a command name to its full path by checking common locations
+pub fn resolve_command(cmd: &str) -> Option<String> {
+    // If it's already an absolute path, check if it exists
+    if cmd.starts_with('/') {
+        if Path::new(cmd).exists() {
+            return Some(cmd.to_string());
+        }
+        return None;
+    }
+
+    // Common paths to search (in order of priority)
+    let search_paths = [
+        "/opt/homebrew/bin",
+        "/usr/local/bin",
+        "/usr/bin",
+        "/bin",
+        "/opt/homebrew/sbin",
+        "/usr/local/sbin",
+        "/usr/sbin",
+        "/sbin",
+        // Add home directory paths for tools like nvm, pyenv, etc.
+        &format!("{}/.nvm/versions/node/v22.9.0/bin", std::env::var("HOME").unwrap_or_default()),
+        &format!("{}/.cargo/bin", std::env::var("HOME").unwrap_or_default()),
+        &format!("{}/.local/bin", std::env::var("HOME").unwrap_or_default()),
+        &format!("{}/Library/pnpm", std::env::var("HOME").unwrap_or_default()),
+    ];
+
+    for path in &search_paths {
+        let full_path = format!("{}/{}", path, cmd);
+        if Path::new(&full_path).exists() {
+            return Some(full_path);
+        }
+    }
+
+    None
+}
+
+/// Build a command for a resolved server executable with a PATH that lets it find
+/// its dependencies, plus the server's own environment with secrets resolved.
+pub fn server_command(command_path: &str, args: &[String], env: &HashMap<String, String>) -> Result<Command> {
+    let mut cmd = Command::new(command_path);
+    for arg in args {
+        cmd.arg(render_for_spawn(arg)?);
+    }
+
+    // Set up a proper PATH so the spawned process can find its dependencies
+    let home = std::env::var("HOME").unwrap_or_default();
+    let path = format!(
+        "/opt/homebrew/bin:/usr/local/bin:/usr/bin:/bin:{}/.cargo/bin:{}/.local/bin:{}/Library/pnpm:{}/.nvm/versions/node/v22.9.0/bin",
+        home, home, home, home
+    );
+    cmd.env("PATH", &path);
+    cmd.env("HOME", &home);
+
+    // Set environment variables from server config
+    for (key, value) in resolve_for_spawn(env)? {
+        cmd.env(key, ConfigManager::expand_path(&render_for_spawn(&value)?));
+    }
+
+    Ok(cmd)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningServer {
+    pub name: String,
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    pub latest: Option<ResourceSample>,
+}
+
+/// Payload of the `server-resources` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerResourceSample {
+    pub name: String,
+    pub sample: ResourceSample,
+}
+
+/// Payload of the `server-exited` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerExit {
+    pub name: String,
+    pub pid: u32,
+    pub code: Option<i32>,
+}
+
+/// Everything observed during one pass of [`ProcessSupervisor::sample_all`].
+#[derive(Debug, Default)]
+pub struct SampleRound {
+    pub samples: Vec<ServerResourceSample>,
+    pub exited: Vec<ServerExit>,
+}
+
+struct SupervisedProcess {
+    child: Child,
+    started_at: DateTime<Utc>,
+    samples: VecDeque<ResourceSample>,
+    last_cpu: Option<(Instant, f64)>,
+}
+
+/// Kill a server and every process in the group it leads, such as the node
+/// process an `npx` wrapper started. Call before waiting on it, while the
+/// group can't have been reused.
+fn kill_group(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        extern "C" {
+            fn kill(pid: i32, signal: i32) -> i32;
+        }
+        const SIGKILL: i32 = 9;
+        // A negative pid signals the whole process group
+        unsafe {
+            kill(-(child.id() as i32), SIGKILL);
+        }
+    }
+    child.kill().ok();
+}
+
+/// Owns the MCP server processes that mcphub launches and records their
+/// resource usage over time.
+pub struct ProcessSupervisor {
+    processes: HashMap<String, SupervisedProcess>,
+    sampler: Box<dyn ResourceSampler>,
+}
+
+impl Default for ProcessSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessSupervisor {
+    pub fn new() -> Self {
+        Self::with_sampler(default_sampler())
+    }
+
+    pub fn with_sampler(sampler: Box<dyn ResourceSampler>) -> Self {
+        ProcessSupervisor {
+            processes: HashMap::new(),
+            sampler,
+        }
+    }
+
+    pub fn start(&mut self, name: &str, server: &MCPServer) -> Result<RunningServer> {
+        if self.processes.contains_key(name) {
+            return Err(AppError::ProcessError(format!("Server is already running: {}", name)));
+        }
+
+        let expanded_command = ConfigManager::expand_path(&server.command);
+        let command_path = resolve_command(&expanded_command)
+            .ok_or_else(|| AppError::ProcessError(format!("Command not found: {}", expanded_command)))?;
+        let args: Vec<String> = server.args.iter().map(|a| ConfigManager::expand_path(a)).collect();
+
+        let mut cmd = server_command(&command_path, &args, &server.env)?;
+        // Lead a process group of its own, so stopping it also stops what it started
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        // Keep stdin open so stdio servers stay alive waiting for a client
+        cmd.stdin(Stdio::piped());
+        cmd.stdout(Stdio::null());
+        cmd.stderr(Stdio::null());
+
+        let child = cmd.spawn().map_err(|e| AppError::ProcessError(e.to_string()))?;
+        let running = RunningServer {
+            name: name.to_string(),
+            pid: child.id(),
+            started_at: Utc::now(),
+            latest: None,
+        };
+
+        self.processes.insert(
+            name.to_string(),
+            SupervisedProcess {
+                child,
+                started_at: running.started_at,
+                samples: VecDeque::new(),
+                last_cpu: None,
+            },
+        );
+
+        Ok(running)
+    }
+
+    pub fn stop(&mut self, name: &str) -> Result<()> {
+        let mut process = self
+            .processes
+            .remove(name)
+            .ok_or_else(|| AppError::ServerNotFound(name.to_string()))?;
+
+        kill_group(&mut process.child);
+        process.child.wait()?;
+        Ok(())
+    }
+
+    pub fn running(&self) -> Vec<RunningServer> {
+        let mut running: Vec<RunningServer> = self
+            .processes
+            .iter()
+            .map(|(name, p)| RunningServer {
+                name: name.clone(),
+                pid: p.child.id(),
+                started_at: p.started_at,
+                latest: p.samples.back().cloned(),
+            })
+            .collect();
+        running.sort_by_key(|r| r.name.to_lowercase());
+        running
+    }
+
+    pub fn samples(&self, name: &str) -> Result<Vec<ResourceSample>> {
+        self.processes
+            .get(name)
+            .map(|p| p.samples.iter().cloned().collect())
+            .ok_or_else(|| AppError::ServerNotFound(name.to_string()))
+    }
+
+    /// Take one sample of every running server and reap any that have exited.
+    pub fn sample_all(&mut self) -> SampleRound {
+        let mut round = SampleRound::default();
+        let mut exited = Vec::new();
+
+        for (name, process) in self.processes.iter_mut() {
+            let pid = process.child.id();
+
+            if let Ok(Some(status)) = process.child.try_wait() {
+                exited.push(name.clone());
+                round.exited.push(ServerExit {
+                    name: name.clone(),
+                    pid,
+                    code: status.code(),
+                });
+                continue;
+            }
+
+            let Ok(snapshot) = self.sampler.sample(pid) else {
+                continue;
+            };
+
+            let now = Instant::now();
+            let cpu_percent = match process.last_cpu {
+                Some((at, cpu_seconds)) => {
+                    let elapsed = now.duration_since(at).as_secs_f64();
+                    if elapsed > 0.0 {
+                        ((snapshot.cpu_seconds - cpu_seconds) / elapsed * 100.0).max(0.0)
+                    } else {
+                        0.0
+                    }
+                }
+                None => 0.0,
+            };
+            process.last_cpu = Some((now, snapshot.cpu_seconds));
+
+            let sample = ResourceSample {
+                timestamp: Utc::now(),
+                pid,
+                cpu_percent,
+                rss_bytes: snapshot.rss_bytes,
+                open_fds: snapshot.open_fds,
+                child_processes: snapshot.child_processes,
+            };
+
+            if process.samples.len() >= MAX_SAMPLES {
+                process.samples.pop_front();
+            }
+            process.samples.push_back(sample.clone());
+            round.samples.push(ServerResourceSample {
+                name: name.clone(),
+                sample,
+            });
+        }
+
+        for name in exited {
+            self.processes.remove(&name);
+        }
+
+        round
+    }
+}
+
+impl Drop for ProcessSupervisor {
+    fn drop(&mut self) {
+        for process in self.processes.values_mut() {
+            kill_group(&mut process.child);
+            process.child.wait().ok();
+        }
+    }
+}
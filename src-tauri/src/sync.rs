@@ -0,0 +1,704 @@
+use crate::config::{AppState, ConfigManager, ConfigType, MCPConfig, MCPServer};
+use crate::diff;
+use crate::error::{AppError, Result};
+use crate::plan::{ChangePlan, Conflict, ConflictVersion};
+use crate::recorder;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+/// How `sync_all` reconciles the clients' server lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncStrategy {
+    /// Every other client gets exactly the source's servers
+    #[default]
+    SourceOfTruth,
+    /// Every client gets every server; the source's definition wins, then the newest
+    Union,
+    /// Every client keeps only the servers that all existing configs have
+    Intersection,
+    /// Every client gets every server, in its most recently changed form
+    PreferNewest,
+    /// Merge each client's edits since the last sync, including deletions;
+    /// servers changed differently in several clients are reported as conflicts
+    ThreeWay,
+}
+
+/// Whole fields that can be pinned; single entries are pinned as `env.KEY` or `headers.KEY`.
+pub const SYNC_FIELDS: &[&str] = &["command", "args", "url", "env", "headers", "alwaysAllow"];
+
+/// Fields of one server that clients keep their own values for when syncing,
+/// e.g. a stricter `alwaysAllow` in Claude Desktop.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRule {
+    /// Field names as in `diff_configs`, per client
+    #[serde(default)]
+    pub pinned: HashMap<ConfigType, Vec<String>>,
+}
+
+impl SyncRule {
+    pub fn is_empty(&self) -> bool {
+        self.pinned.values().all(|fields| fields.is_empty())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for field in self.pinned.values().flatten() {
+            let known = match field.split_once('.') {
+                Some((map, key)) => (map == "env" || map == "headers") && !key.is_empty(),
+                None => SYNC_FIELDS.contains(&field.as_str()),
+            };
+            if !known {
+                return Err(AppError::InvalidArgument(format!("Unknown field: {}", field)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn keep_entry(incoming: &mut HashMap<String, String>, existing: &HashMap<String, String>, key: &str) {
+    match existing.get(key) {
+        Some(value) => incoming.insert(key.to_string(), value.clone()),
+        None => incoming.remove(key),
+    };
+}
+
+/// `incoming` with the `pinned` fields of the client's `existing` copy kept as
+/// they are, absent entries included. A server the client doesn't have yet is
+/// taken whole.
+pub fn keep_pinned(incoming: &MCPServer, existing: Option<&MCPServer>, pinned: &[String]) -> MCPServer {
+    let mut server = incoming.clone();
+    let Some(existing) = existing else {
+        return server;
+    };
+
+    for field in pinned {
+        match (field.as_str(), field.split_once('.')) {
+            (_, Some(("env", key))) => keep_entry(&mut server.env, &existing.env, key),
+            (_, Some(("headers", key))) => keep_entry(&mut server.headers, &existing.headers, key),
+            ("command", _) => server.command = existing.command.clone(),
+            ("args", _) => server.args = existing.args.clone(),
+            ("url", _) => server.url = existing.url.clone(),
+            ("env", _) => server.env = existing.env.clone(),
+            ("headers", _) => server.headers = existing.headers.clone(),
+            ("alwaysAllow", _) => server.always_allow = existing.always_allow.clone(),
+            _ => {}
+        }
+    }
+    server
+}
+
+/// Whether two copies of a server match apart from the `pinned` fields.
+pub fn same_unpinned(a: Option<&MCPServer>, b: Option<&MCPServer>, pinned: &[String]) -> bool {
+    let blank = MCPServer::default();
+    let strip = |server: &MCPServer| keep_pinned(server, Some(&blank), pinned);
+    same(a.map(strip).as_ref(), b.map(strip).as_ref())
+}
+
+/// Keep each client's pinned fields in the servers it's about to get, along
+/// with the fields pinned in the client each server was copied from. Servers
+/// disabled in a client are left out, so syncing doesn't enable them.
+fn pin_desired(
+    manager: &ConfigManager,
+    configs: &[MCPConfig],
+    current: &HashMap<ConfigType, HashMap<String, MCPServer>>,
+    desired: &mut HashMap<ConfigType, HashMap<String, MCPServer>>,
+    origin: &HashMap<String, ConfigType>,
+) {
+    let state = manager.get_state();
+    for config in configs {
+        if let Some(servers) = desired.get_mut(&config.config_type) {
+            servers.retain(|name, _| !config.disabled.contains_key(name));
+        }
+    }
+    for (config_type, servers) in desired.iter_mut() {
+        for (name, server) in servers.iter_mut() {
+            let mut pinned = state.pinned(name, *config_type).to_vec();
+            if let Some(from) = origin.get(name) {
+                pinned.extend_from_slice(state.pinned(name, *from));
+            }
+            let existing = current.get(config_type).and_then(|s| s.get(name));
+            *server = keep_pinned(server, existing, &pinned);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerChange {
+    fingerprint: String,
+    changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ChangeFile {
+    #[serde(default)]
+    servers: HashMap<String, HashMap<ConfigType, ServerChange>>,
+}
+
+/// Stable hash of a server's normalized definition, so values never reach disk.
+fn fingerprint(server: &MCPServer) -> String {
+    let server = diff::normalize(&recorder::unproxied(server));
+    let mut allowed = server.always_allow.clone();
+    allowed.sort();
+    let canonical = (
+        &server.command,
+        &server.args,
+        server.env.iter().collect::<BTreeMap<_, _>>(),
+        &server.url,
+        server.headers.iter().collect::<BTreeMap<_, _>>(),
+        allowed,
+    );
+    let bytes = serde_json::to_vec(&canonical).unwrap_or_default();
+    Sha256::digest(bytes).iter().take(16).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// When each server last changed in each client, in `~/.mcphub/changes.json`.
+///
+/// mcphub's own writes are stamped with the time of the write. Edits made
+/// elsewhere are noticed the next time the config is read and stamped with
+/// the file's modification time, the best estimate available.
+pub struct ChangeLog {
+    path: PathBuf,
+    file: ChangeFile,
+}
+
+impl ChangeLog {
+    pub fn open() -> Result<Self> {
+        let dir = dirs::home_dir()
+            .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+            .join(".mcphub");
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("changes.json");
+        let file = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?).unwrap_or_default()
+        } else {
+            ChangeFile::default()
+        };
+        Ok(ChangeLog { path, file })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.file)?)?;
+        Ok(())
+    }
+
+    /// Record the servers a client has now, stamping any that changed with `at`.
+    /// Only in memory, so reads can use it; returns whether anything changed,
+    /// for writes to [`save`](Self::save).
+    pub fn observe(&mut self, config_type: ConfigType, servers: &HashMap<String, MCPServer>, at: DateTime<Utc>) -> bool {
+        let mut dirty = false;
+
+        for (name, server) in servers {
+            let fingerprint = fingerprint(server);
+            let entry = self.file.servers.entry(name.clone()).or_default();
+            if entry.get(&config_type).is_some_and(|c| c.fingerprint == fingerprint) {
+                continue;
+            }
+            entry.insert(config_type, ServerChange { fingerprint, changed_at: at });
+            dirty = true;
+        }
+
+        for (name, entry) in self.file.servers.iter_mut() {
+            if !servers.contains_key(name) && entry.remove(&config_type).is_some() {
+                dirty = true;
+            }
+        }
+        self.file.servers.retain(|_, entry| !entry.is_empty());
+        dirty
+    }
+
+    pub fn changed_at(&self, name: &str, config_type: ConfigType) -> Option<DateTime<Utc>> {
+        self.file.servers.get(name)?.get(&config_type).map(|c| c.changed_at)
+    }
+
+    /// Keep a server's change times across a rename, once the renamed configs
+    /// are written. The write stamped the new name as a fresh change; copies it
+    /// left as they were get back the times `before` had for the old name.
+    pub fn rename(&mut self, before: &ChangeLog, old_name: &str, new_name: &str) -> Result<()> {
+        let (Some(old), Some(new)) = (before.file.servers.get(old_name), self.file.servers.get_mut(new_name)) else {
+            return Ok(());
+        };
+        let mut dirty = false;
+        for (config_type, change) in new.iter_mut() {
+            if let Some(kept) = old.get(config_type).filter(|c| c.fingerprint == change.fingerprint) {
+                change.changed_at = kept.changed_at;
+                dirty = true;
+            }
+        }
+        if dirty {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct BaselineFile {
+    #[serde(default)]
+    servers: HashMap<String, MCPServer>,
+}
+
+/// The servers every client agreed on at the last sync, in `~/.mcphub/baseline.json`.
+///
+/// Secret values are stored as vault references, the way `export_config` does.
+pub struct Baseline {
+    path: PathBuf,
+    file: BaselineFile,
+}
+
+impl Baseline {
+    pub fn open() -> Result<Self> {
+        let dir = dirs::home_dir()
+            .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+            .join(".mcphub");
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("baseline.json");
+        let file = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path)?).unwrap_or_default()
+        } else {
+            BaselineFile::default()
+        };
+        Ok(Baseline { path, file })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MCPServer> {
+        self.file.servers.get(name)
+    }
+
+    pub fn rename(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        if let Some(server) = self.file.servers.remove(old_name) {
+            self.file.servers.insert(new_name.to_string(), server);
+            fs::write(&self.path, serde_json::to_string_pretty(&self.file)?)?;
+        }
+        Ok(())
+    }
+
+    /// Snapshot every server the clients agree on, and forget those that are
+    /// gone from all of them. Servers they disagree about keep their old snapshot.
+    pub fn record(&mut self, agreement: &Agreement) -> Result<()> {
+        for (name, (_, server)) in &agreement.servers {
+            self.file.servers.insert(name.clone(), server.clone());
+        }
+        self.file.servers.retain(|name, _| agreement.present.contains(name));
+
+        fs::write(&self.path, serde_json::to_string_pretty(&self.file)?)?;
+        Ok(())
+    }
+}
+
+/// What the existing client configs agree on, apart from pinned fields.
+pub struct Agreement {
+    /// Servers every client has the same copy of, with the first such client
+    /// and its copy, secrets as vault references
+    pub servers: HashMap<String, (ConfigType, MCPServer)>,
+    /// Servers at least one client has, enabled or disabled
+    pub present: BTreeSet<String>,
+}
+
+impl Agreement {
+    /// `None` if no client has a config yet.
+    pub fn of(manager: &ConfigManager) -> Result<Option<Self>> {
+        let copies = referenced_servers(manager)?;
+        if copies.is_empty() {
+            return Ok(None);
+        }
+
+        let present: BTreeSet<String> = copies.values().flat_map(|s| s.keys()).cloned().collect();
+        let mut servers = HashMap::new();
+        for name in &present {
+            let pinned = manager.get_state().all_pinned(name);
+            let mut versions = ConfigType::all()
+                .into_iter()
+                .filter_map(|ct| copies.get(&ct).map(|s| (ct, s.get(name))));
+            let Some((first, Some(copy))) = versions.next() else {
+                continue;
+            };
+            if versions.all(|(_, v)| same_unpinned(v, Some(copy), &pinned)) {
+                servers.insert(name.clone(), (first, copy.clone()));
+            }
+        }
+        Ok(Some(Agreement { servers, present }))
+    }
+}
+
+/// Servers of every existing client config, disabled ones included, with
+/// secrets as vault references.
+fn referenced_servers(manager: &ConfigManager) -> Result<HashMap<ConfigType, HashMap<String, MCPServer>>> {
+    let mut copies = HashMap::new();
+    for config_type in ConfigType::all() {
+        let config = recorder::unproxied_config(manager.read_config(config_type)?);
+        if config.exists {
+            let mut servers = config.servers;
+            servers.extend(config.disabled);
+            copies.insert(config_type, ConfigManager::reference_secrets(&servers));
+        }
+    }
+    Ok(copies)
+}
+
+/// Whether two copies of a server are equivalent, counting absence as a value.
+fn same(a: Option<&MCPServer>, b: Option<&MCPServer>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => diff::diff_servers(a, b).is_empty(),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+fn display_names(config_types: &[ConfigType]) -> String {
+    config_types.iter().map(|ct| ct.display_name()).collect::<Vec<_>>().join(", ")
+}
+
+/// What a three-way merge makes of one server.
+enum Merged {
+    /// Every client gets this copy, or loses the server if `None`
+    Settled(Option<MCPServer>, String),
+    Conflict(Conflict),
+}
+
+/// Merge one server's copies against its baseline `base`.
+///
+/// `copies` holds each existing client's copy, disabled or not, in client
+/// order; `configs` the clients' own configs, where the winning copy is
+/// taken from so its secrets stay as the client has them.
+fn merge_server(
+    name: &str,
+    base: Option<&MCPServer>,
+    copies: &[(ConfigType, Option<&MCPServer>)],
+    configs: &[MCPConfig],
+    state: &AppState,
+    resolution: Option<ConfigType>,
+) -> Merged {
+    // Clients that changed the server since the last sync, in client order.
+    // Their own pinned fields don't count as changes.
+    let edits: Vec<(ConfigType, Option<&MCPServer>)> = copies
+        .iter()
+        .copied()
+        .filter(|(ct, copy)| !same_unpinned(*copy, base, state.pinned(name, *ct)))
+        .collect();
+
+    // The winning copy, minus the fields pinned in the client it came from.
+    // It may be disabled there, so look in both halves of its config
+    let take = |from: ConfigType| {
+        configs
+            .iter()
+            .find(|c| c.config_type == from)
+            .and_then(|c| c.servers.get(name).or_else(|| c.disabled.get(name)))
+            .map(|server| keep_pinned(server, base, state.pinned(name, from)))
+    };
+
+    if let Some(from) = resolution {
+        return Merged::Settled(take(from), format!("Resolved from {}", from.display_name()));
+    }
+    let Some(&(first, copy)) = edits.first() else {
+        return Merged::Settled(base.cloned(), "Unchanged since last sync".to_string());
+    };
+
+    let all_pinned = state.all_pinned(name);
+    if !edits.iter().all(|(_, other)| same_unpinned(*other, copy, &all_pinned)) {
+        return Merged::Conflict(Conflict {
+            server: name.to_string(),
+            base: base.cloned(),
+            versions: edits
+                .iter()
+                .map(|(config_type, copy)| ConflictVersion {
+                    config_type: *config_type,
+                    server: copy.cloned(),
+                    fields: match (base, copy) {
+                        (Some(b), Some(c)) => diff::diff_servers(b, c),
+                        _ => Vec::new(),
+                    },
+                })
+                .collect(),
+        });
+    }
+
+    let editors: Vec<ConfigType> = edits.iter().map(|(ct, _)| *ct).collect();
+    let verb = match (base, copy) {
+        (None, _) => "Added",
+        (_, None) => "Removed",
+        _ => "Changed",
+    };
+    Merged::Settled(take(first), format!("{} in {}", verb, display_names(&editors)))
+}
+
+/// Plan a three-way merge of every client against the baseline.
+///
+/// A client whose copy of a server matches the baseline hasn't touched it, so
+/// any other client's edit or deletion wins. If every client that touched it
+/// ended up with the same copy, that copy wins too. Otherwise it's a conflict
+/// and stays as it is until `resolutions` names the client whose copy to keep.
+fn three_way(
+    manager: &ConfigManager,
+    configs: &[MCPConfig],
+    current: &HashMap<ConfigType, HashMap<String, MCPServer>>,
+    resolutions: &HashMap<String, ConfigType>,
+) -> Result<ChangePlan> {
+    let baseline = Baseline::open()?;
+    let copies = referenced_servers(manager)?;
+    let state = manager.get_state();
+
+    let names: BTreeSet<&String> = current
+        .values()
+        .flat_map(|s| s.keys())
+        .chain(baseline.file.servers.keys())
+        .collect();
+
+    let mut desired: HashMap<ConfigType, HashMap<String, MCPServer>> =
+        ConfigType::all().into_iter().map(|ct| (ct, HashMap::new())).collect();
+    let mut reasons: HashMap<String, String> = HashMap::new();
+    let mut conflicts = Vec::new();
+
+    for name in names {
+        let versions: Vec<(ConfigType, Option<&MCPServer>)> = ConfigType::all()
+            .into_iter()
+            .filter_map(|ct| copies.get(&ct).map(|s| (ct, s.get(name))))
+            .collect();
+        let merged = merge_server(name, baseline.get(name), &versions, configs, state, resolutions.get(name).copied());
+
+        match merged {
+            Merged::Conflict(conflict) => {
+                for (config_type, servers) in current {
+                    if let Some(server) = servers.get(name) {
+                        desired.entry(*config_type).or_default().insert(name.clone(), server.clone());
+                    }
+                }
+                conflicts.push(conflict);
+            }
+            Merged::Settled(result, reason) => {
+                if let Some(server) = result {
+                    for servers in desired.values_mut() {
+                        servers.insert(name.clone(), server.clone());
+                    }
+                }
+                reasons.insert(name.clone(), reason);
+            }
+        }
+    }
+
+    pin_desired(manager, configs, current, &mut desired, &HashMap::new());
+    let mut plan = ChangePlan::from_states(current, &desired, |name, _, _| {
+        reasons.get(name).cloned().unwrap_or_default()
+    });
+    plan.conflicts = conflicts;
+    Ok(plan)
+}
+
+/// When a client's copy of a server last changed: tracked if known, else the file's mtime.
+fn changed_at(log: &ChangeLog, config: &MCPConfig, name: &str) -> Option<DateTime<Utc>> {
+    log.changed_at(name, config.config_type).or(config.last_modified)
+}
+
+/// Plan a sync of every client from `source` using `strategy`.
+///
+/// Source of truth only writes the other clients; the merging strategies can
+/// change the source as well, so all clients end up with the same servers.
+/// Three-way merges treat every client alike and ignore `source`; conflicts
+/// they find are settled by `resolutions`, server name to the winning client.
+pub fn plan(
+    manager: &ConfigManager,
+    source: ConfigType,
+    strategy: SyncStrategy,
+    resolutions: &HashMap<String, ConfigType>,
+) -> Result<ChangePlan> {
+    let configs: Vec<MCPConfig> = ConfigType::all()
+        .into_iter()
+        .map(|ct| manager.read_config(ct).map(recorder::unproxied_config))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut log = ChangeLog::open()?;
+    for config in configs.iter().filter(|c| c.exists) {
+        log.observe(config.config_type, &config.servers, config.last_modified.unwrap_or_else(Utc::now));
+    }
+
+    let current: HashMap<ConfigType, HashMap<String, MCPServer>> =
+        configs.iter().map(|c| (c.config_type, c.servers.clone())).collect();
+    if strategy == SyncStrategy::ThreeWay {
+        return three_way(manager, &configs, &current, resolutions);
+    }
+    let source_servers = &current[&source];
+
+    // Where each chosen definition comes from
+    let mut origin: HashMap<String, ConfigType> = HashMap::new();
+    let names: BTreeSet<&String> = current.values().flat_map(|s| s.keys()).collect();
+
+    // The client whose copy of `name` changed most recently
+    let newest = |name: &str| -> ConfigType {
+        let mut holders: Vec<&MCPConfig> = configs.iter().filter(|c| c.servers.contains_key(name)).collect();
+        // Source first, so it wins ties
+        holders.sort_by_key(|c| c.config_type != source);
+
+        let mut best: Option<(ConfigType, Option<DateTime<Utc>>)> = None;
+        for config in holders {
+            let at = changed_at(&log, config, name);
+            match best {
+                Some((_, best_at)) if at <= best_at => {}
+                _ => best = Some((config.config_type, at)),
+            }
+        }
+        best.map_or(source, |(ct, _)| ct)
+    };
+
+    match strategy {
+        SyncStrategy::SourceOfTruth => {
+            for name in source_servers.keys() {
+                origin.insert(name.clone(), source);
+            }
+        }
+        SyncStrategy::Union => {
+            for name in names {
+                let from = if source_servers.contains_key(name) { source } else { newest(name) };
+                origin.insert(name.clone(), from);
+            }
+        }
+        SyncStrategy::Intersection => {
+            for name in source_servers.keys() {
+                // A server disabled somewhere still counts as being there
+                let everywhere = configs
+                    .iter()
+                    .filter(|c| c.exists)
+                    .all(|c| c.servers.contains_key(name) || c.disabled.contains_key(name));
+                if everywhere {
+                    origin.insert(name.clone(), source);
+                }
+            }
+        }
+        SyncStrategy::PreferNewest => {
+            for name in names {
+                origin.insert(name.clone(), newest(name));
+            }
+        }
+        SyncStrategy::ThreeWay => unreachable!("planned by three_way"),
+    }
+
+    let chosen: HashMap<String, MCPServer> = origin
+        .iter()
+        .map(|(name, from)| (name.clone(), current[from][name].clone()))
+        .collect();
+
+    let mut desired: HashMap<ConfigType, HashMap<String, MCPServer>> = ConfigType::all()
+        .into_iter()
+        .filter(|ct| strategy != SyncStrategy::SourceOfTruth || *ct != source)
+        .map(|ct| (ct, chosen.clone()))
+        .collect();
+    pin_desired(manager, &configs, &current, &mut desired, &origin);
+
+    Ok(ChangePlan::from_states(&current, &desired, |name, _, after| match (after, origin.get(name)) {
+        (Some(_), Some(from)) if *from == source => format!("From {}", from.display_name()),
+        (Some(_), Some(from)) => format!("Newest in {}", from.display_name()),
+        _ if strategy == SyncStrategy::Intersection => "Not in every tool".to_string(),
+        _ => format!("Not in {}", source.display_name()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(command: &str) -> MCPServer {
+        MCPServer {
+            command: command.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn config(config_type: ConfigType, enabled: &[(&str, &str)], disabled: &[(&str, &str)]) -> MCPConfig {
+        let servers = |list: &[(&str, &str)]| list.iter().map(|(n, c)| (n.to_string(), server(c))).collect();
+        MCPConfig {
+            config_type,
+            path: String::new(),
+            exists: true,
+            is_valid: true,
+            servers: servers(enabled),
+            disabled: servers(disabled),
+            last_modified: None,
+            error: None,
+        }
+    }
+
+    /// Each client's copy of `name`, enabled or disabled, as `three_way` passes them.
+    fn copies<'a>(configs: &'a [MCPConfig], name: &str) -> Vec<(ConfigType, Option<&'a MCPServer>)> {
+        configs
+            .iter()
+            .map(|c| (c.config_type, c.servers.get(name).or_else(|| c.disabled.get(name))))
+            .collect()
+    }
+
+    fn merge(configs: &[MCPConfig], base: Option<&MCPServer>, resolution: Option<ConfigType>) -> Merged {
+        merge_server("api", base, &copies(configs, "api"), configs, &AppState::default(), resolution)
+    }
+
+    #[test]
+    fn untouched_server_keeps_its_baseline() {
+        let configs = [
+            config(ConfigType::ClaudeCode, &[("api", "old")], &[]),
+            config(ConfigType::ClaudeDesktop, &[("api", "old")], &[]),
+        ];
+        let Merged::Settled(result, _) = merge(&configs, Some(&server("old")), None) else {
+            panic!("expected no conflict");
+        };
+        assert_eq!(result, Some(server("old")));
+    }
+
+    #[test]
+    fn deletion_in_one_client_propagates() {
+        let configs = [
+            config(ConfigType::ClaudeCode, &[("api", "old")], &[]),
+            config(ConfigType::ClaudeDesktop, &[], &[]),
+        ];
+        let Merged::Settled(result, reason) = merge(&configs, Some(&server("old")), None) else {
+            panic!("expected no conflict");
+        };
+        assert_eq!(result, None);
+        assert_eq!(reason, "Removed in Claude Desktop");
+    }
+
+    #[test]
+    fn different_edits_conflict() {
+        let configs = [
+            config(ConfigType::ClaudeCode, &[("api", "left")], &[]),
+            config(ConfigType::ClaudeDesktop, &[("api", "right")], &[]),
+            config(ConfigType::RooCode, &[("api", "old")], &[]),
+        ];
+        let Merged::Conflict(conflict) = merge(&configs, Some(&server("old")), None) else {
+            panic!("expected a conflict");
+        };
+        let clients: Vec<ConfigType> = conflict.versions.iter().map(|v| v.config_type).collect();
+        assert_eq!(clients, vec![ConfigType::ClaudeCode, ConfigType::ClaudeDesktop]);
+    }
+
+    #[test]
+    fn conflict_resolves_to_a_disabled_copy() {
+        let configs = [
+            config(ConfigType::ClaudeCode, &[("api", "left")], &[]),
+            config(ConfigType::ClaudeDesktop, &[], &[("api", "right")]),
+        ];
+        let resolved = merge(&configs, Some(&server("old")), Some(ConfigType::ClaudeDesktop));
+        let Merged::Settled(result, reason) = resolved else {
+            panic!("expected the resolution to settle it");
+        };
+        assert_eq!(result, Some(server("right")));
+        assert_eq!(reason, "Resolved from Claude Desktop");
+    }
+
+    #[test]
+    fn edit_to_a_disabled_copy_propagates() {
+        let configs = [
+            config(ConfigType::ClaudeCode, &[("api", "old")], &[]),
+            config(ConfigType::ClaudeDesktop, &[], &[("api", "new")]),
+        ];
+        let Merged::Settled(result, reason) = merge(&configs, Some(&server("old")), None) else {
+            panic!("expected no conflict");
+        };
+        assert_eq!(result, Some(server("new")));
+        assert_eq!(reason, "Changed in Claude Desktop");
+    }
+}
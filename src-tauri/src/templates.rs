@@ -0,0 +1,740 @@
+use crate::config::{ConfigManager, ConfigType, MCPServer};
+use crate::error::{AppError, Result};
+use crate::plan::{self, ChangePlan, PlannedChange};
+use crate::recorder;
+use crate::registry::Registry;
+use crate::secrets::{secret_name, secret_ref, SecretVault};
+use crate::sync::keep_pinned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The catalog that ships with mcphub.
+const BUILTIN: &str = include_str!("../templates/builtin.json");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ParameterKind {
+    #[default]
+    Text,
+    /// A file or directory that must exist; `~` is expanded
+    Path,
+    /// Stored in the vault, and only usable in `env`
+    Secret,
+    Url,
+    /// One of the parameter's `options`
+    Enum,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateParameter {
+    /// Referenced in the template's server as `{{name}}`
+    pub name: String,
+    pub label: String,
+    #[serde(default)]
+    pub kind: ParameterKind,
+    pub description: Option<String>,
+    #[serde(default = "default_required")]
+    pub required: bool,
+    pub default: Option<String>,
+    /// Allowed values of an enum parameter
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Allowed schemes of a URL parameter; http and https if empty
+    #[serde(default)]
+    pub schemes: Vec<String>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+/// A server definition with `{{parameter}}` placeholders, filled in from a form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTemplate {
+    pub id: String,
+    /// Raise it whenever the server definition changes, so servers made from
+    /// an older version are offered an upgrade
+    #[serde(default = "default_version")]
+    pub version: u32,
+    pub name: String,
+    pub description: String,
+    pub source_url: Option<String>,
+    #[serde(default)]
+    pub parameters: Vec<TemplateParameter>,
+    pub server: MCPServer,
+    /// File the template was loaded from; `None` for a built-in. Set by
+    /// mcphub, whatever the file says
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Whether it replaces a built-in template with the same id
+    #[serde(default)]
+    pub overrides_builtin: bool,
+}
+
+/// A template file that couldn't be loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateProblem {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Catalog {
+    /// Sorted by id
+    pub templates: Vec<ServerTemplate>,
+    pub problems: Vec<TemplateProblem>,
+}
+
+/// A value a template can't be filled in with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterIssue {
+    pub parameter: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstantiateRequest {
+    pub template_id: String,
+    /// Server name; the template's id if omitted
+    pub name: Option<String>,
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    pub targets: Vec<ConfigType>,
+}
+
+/// Every string of the server that may hold placeholders, with whether it's an env value.
+fn server_values(server: &mut MCPServer) -> impl Iterator<Item = (&mut String, bool)> {
+    std::iter::once((&mut server.command, false))
+        .chain(server.args.iter_mut().map(|a| (a, false)))
+        .chain(server.url.iter_mut().map(|u| (u, false)))
+        .chain(server.headers.values_mut().map(|v| (v, false)))
+        .chain(server.env.values_mut().map(|v| (v, true)))
+}
+
+/// The `{{...}}` placeholders in a value: where each one is, braces
+/// included, and the name inside, trimmed.
+fn placeholder_spans(value: &str) -> Vec<(Range<usize>, &str)> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = value[offset..].find("{{").map(|i| offset + i) {
+        let Some(end) = value[start..].find("}}").map(|i| start + i + 2) else {
+            break;
+        };
+        spans.push((start..end, value[start + 2..end - 2].trim()));
+        offset = end;
+    }
+    spans
+}
+
+/// Names of the `{{...}}` placeholders in a value.
+fn placeholders(value: &str) -> Vec<&str> {
+    placeholder_spans(value).into_iter().map(|(_, name)| name).collect()
+}
+
+/// Check that a template only uses parameters it declares, and secrets only in `env`.
+fn check(template: &ServerTemplate) -> Result<()> {
+    let invalid = |message: String| AppError::InvalidArgument(format!("Template {}: {}", template.id, message));
+
+    for parameter in &template.parameters {
+        if parameter.kind == ParameterKind::Enum && parameter.options.is_empty() {
+            return Err(invalid(format!("enum parameter {} has no options", parameter.name)));
+        }
+    }
+
+    let mut server = template.server.clone();
+    for (value, is_env) in server_values(&mut server) {
+        for name in placeholders(value) {
+            let parameter = template
+                .parameters
+                .iter()
+                .find(|p| p.name == name)
+                .ok_or_else(|| invalid(format!("unknown parameter {}", name)))?;
+            if parameter.kind == ParameterKind::Secret && !is_env {
+                return Err(invalid(format!("secret {} can only be used in env", name)));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_template_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|e| e.to_str()), Some("json" | "toml"))
+}
+
+/// Template files under `dir` and its subdirectories, sorted, skipping hidden
+/// entries such as `.git`.
+fn template_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(template_files(&path)?);
+        } else if is_template_file(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// One template per file, as JSON or TOML.
+fn load_file(path: &Path) -> std::result::Result<ServerTemplate, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut template: ServerTemplate = if path.extension().is_some_and(|e| e == "toml") {
+        toml::from_str(&content).map_err(|e| e.to_string())?
+    } else {
+        serde_json::from_str(&content).map_err(|e| e.to_string())?
+    };
+    check(&template).map_err(|e| e.to_string())?;
+    template.source = Some(path.to_string_lossy().to_string());
+    template.overrides_builtin = false;
+    Ok(template)
+}
+
+/// Where templates that came with imported bundles are kept.
+pub fn bundled_dir() -> Result<PathBuf> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| AppError::PathError("Could not find home directory".to_string()))?
+        .join(".mcphub")
+        .join("templates"))
+}
+
+/// `dirs` after the bundled templates directory, if there is one, so the
+/// configured directories override bundled templates.
+fn with_bundled(dirs: &[String]) -> Vec<String> {
+    let bundled = bundled_dir().ok().filter(|dir| dir.is_dir());
+    bundled
+        .map(|dir| dir.to_string_lossy().to_string())
+        .into_iter()
+        .chain(dirs.iter().cloned())
+        .collect()
+}
+
+/// A template as it would be saved, without what mcphub sets on loading.
+fn portable(template: &ServerTemplate) -> ServerTemplate {
+    let mut template = template.clone();
+    template.source = None;
+    template.overrides_builtin = false;
+    template
+}
+
+/// Keep a template that came with a bundle unless the catalog already has
+/// the same or a newer version of it. Only a bundle signed by an already
+/// trusted key may replace a built-in or a template from `dirs`; from any
+/// other bundle a different template with such an id is left out. Returns
+/// whether it was kept or the catalog already had it.
+pub fn keep_bundled(template: &ServerTemplate, dirs: &[String], trusted: bool) -> Result<bool> {
+    check(template)?;
+    let template = portable(template);
+    let dir = bundled_dir()?;
+    if let Some(known) = catalog(dirs)?.templates.into_iter().find(|t| t.id == template.id) {
+        if serde_json::to_value(portable(&known))? == serde_json::to_value(&template)? {
+            return Ok(true);
+        }
+        let bundled = known.source.as_deref().is_some_and(|source| Path::new(source).starts_with(&dir));
+        if !trusted && (!bundled || known.overrides_builtin) {
+            return Ok(false);
+        }
+        if known.version >= template.version {
+            return Ok(true);
+        }
+    }
+
+    fs::create_dir_all(&dir)?;
+    let file: String = template
+        .id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_".contains(c) { c } else { '-' })
+        .collect();
+    fs::write(dir.join(format!("{}.json", file)), serde_json::to_string_pretty(&template)?)?;
+    Ok(true)
+}
+
+/// The built-in templates, overridden by id by bundled ones and then by those
+/// in `dirs`. A later directory overrides an earlier one; within a directory
+/// the first file with an id wins. Files are read on every call, so edits
+/// show up straight away.
+pub fn catalog(dirs: &[String]) -> Result<Catalog> {
+    let mut templates: BTreeMap<String, ServerTemplate> = BTreeMap::new();
+    for template in serde_json::from_str::<Vec<ServerTemplate>>(BUILTIN)? {
+        check(&template)?;
+        templates.insert(template.id.clone(), template);
+    }
+
+    let mut problems = Vec::new();
+    let mut problem = |path: &Path, message: String| {
+        problems.push(TemplateProblem {
+            path: path.to_string_lossy().to_string(),
+            message,
+        })
+    };
+    for dir in &with_bundled(dirs) {
+        let dir = PathBuf::from(ConfigManager::expand_path(dir));
+        let files = match template_files(&dir) {
+            Ok(files) => files,
+            Err(e) => {
+                problem(&dir, e.to_string());
+                continue;
+            }
+        };
+
+        let mut seen: HashMap<String, PathBuf> = HashMap::new();
+        for path in files {
+            let mut template = match load_file(&path) {
+                Ok(template) => template,
+                Err(message) => {
+                    problem(&path, message);
+                    continue;
+                }
+            };
+            if let Some(first) = seen.get(&template.id) {
+                problem(&path, format!("Template {} is already defined in {}", template.id, first.display()));
+                continue;
+            }
+            seen.insert(template.id.clone(), path);
+
+            let replaced = templates.get(&template.id);
+            template.overrides_builtin = replaced.is_some_and(|t| t.source.is_none() || t.overrides_builtin);
+            templates.insert(template.id.clone(), template);
+        }
+    }
+
+    Ok(Catalog {
+        templates: templates.into_values().collect(),
+        problems,
+    })
+}
+
+pub fn find(dirs: &[String], id: &str) -> Result<ServerTemplate> {
+    catalog(dirs)?
+        .templates
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| AppError::InvalidArgument(format!("No template named {}", id)))
+}
+
+/// Notices when template files in the configured directories are added,
+/// edited or removed, by polling modification times like `ConfigWatcher`.
+#[derive(Default)]
+pub struct TemplateWatcher {
+    seen: Option<BTreeMap<PathBuf, Option<SystemTime>>>,
+}
+
+impl TemplateWatcher {
+    /// Whether anything changed since the last poll. The first poll only
+    /// records the current state.
+    pub fn poll(&mut self, dirs: &[String]) -> bool {
+        let mut current = BTreeMap::new();
+        for dir in &with_bundled(dirs) {
+            let dir = PathBuf::from(ConfigManager::expand_path(dir));
+            for path in template_files(&dir).unwrap_or_default() {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                current.insert(path, modified);
+            }
+        }
+        match self.seen.replace(current) {
+            Some(previous) => Some(&previous) != self.seen.as_ref(),
+            None => false,
+        }
+    }
+}
+
+/// The value a parameter takes: the given one, else its default.
+fn value_of<'a>(parameter: &'a TemplateParameter, values: &'a HashMap<String, String>) -> Option<&'a str> {
+    values
+        .get(&parameter.name)
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .or(parameter.default.as_deref())
+}
+
+fn check_value(parameter: &TemplateParameter, value: &str) -> Option<String> {
+    match parameter.kind {
+        ParameterKind::Text => None,
+        ParameterKind::Path => {
+            let path = ConfigManager::expand_path(value);
+            (!Path::new(&path).exists()).then(|| format!("No such file or directory: {}", path))
+        }
+        ParameterKind::Secret => {
+            let name = secret_name(value)?;
+            let known = SecretVault::open().is_ok_and(|vault| vault.get(name).is_ok());
+            (!known).then(|| format!("No secret named {}", name))
+        }
+        ParameterKind::Url => {
+            let schemes = if parameter.schemes.is_empty() {
+                vec!["http".to_string(), "https".to_string()]
+            } else {
+                parameter.schemes.clone()
+            };
+            let valid = value
+                .split_once("://")
+                .is_some_and(|(scheme, rest)| schemes.contains(&scheme.to_lowercase()) && !rest.is_empty());
+            (!valid).then(|| format!("Expected a URL starting with {}://", schemes.join(":// or ")))
+        }
+        ParameterKind::Enum => (!parameter.options.iter().any(|o| o == value))
+            .then(|| format!("Expected one of: {}", parameter.options.join(", "))),
+    }
+}
+
+/// Every problem with filling `template` in with `values`.
+pub fn validate(template: &ServerTemplate, values: &HashMap<String, String>) -> Vec<ParameterIssue> {
+    let mut issues = Vec::new();
+    for parameter in &template.parameters {
+        let message = match value_of(parameter, values) {
+            Some(value) => check_value(parameter, value),
+            None if parameter.required => Some("Required".to_string()),
+            None => None,
+        };
+        if let Some(message) = message {
+            issues.push(ParameterIssue {
+                parameter: parameter.name.clone(),
+                message,
+            });
+        }
+    }
+
+    let mut unknown: Vec<&String> = values
+        .keys()
+        .filter(|key| !template.parameters.iter().any(|p| &p.name == *key))
+        .collect();
+    unknown.sort();
+    for key in unknown {
+        issues.push(ParameterIssue {
+            parameter: key.clone(),
+            message: "Unknown parameter".to_string(),
+        });
+    }
+    issues
+}
+
+fn ensure_valid(template: &ServerTemplate, values: &HashMap<String, String>) -> Result<()> {
+    let issues = validate(template, values);
+    if issues.is_empty() {
+        return Ok(());
+    }
+    let issues: Vec<String> = issues.iter().map(|i| format!("{}: {}", i.parameter, i.message)).collect();
+    Err(AppError::InvalidArgument(issues.join("; ")))
+}
+
+/// Fill the placeholders in, without checking the values. Entries whose
+/// placeholders are all left empty are dropped, so optional parameters leave
+/// no blank args or env vars behind.
+fn fill_in(template: &ServerTemplate, values: &HashMap<String, String>) -> MCPServer {
+    let resolved: HashMap<&str, &str> = template
+        .parameters
+        .iter()
+        .map(|p| (p.name.as_str(), value_of(p, values).unwrap_or_default()))
+        .collect();
+    // Each placeholder is replaced where it was found, whatever the spacing
+    // inside its braces, and values are never searched for placeholders
+    let fill = |value: &str| {
+        let mut filled = String::new();
+        let mut last = 0;
+        for (span, name) in placeholder_spans(value) {
+            filled.push_str(&value[last..span.start]);
+            filled.push_str(resolved.get(name).unwrap_or(&""));
+            last = span.end;
+        }
+        filled.push_str(&value[last..]);
+        filled
+    };
+    let kept = |value: &String| {
+        let names = placeholders(value);
+        names.is_empty() || names.iter().any(|name| resolved.get(name).is_some_and(|v| !v.is_empty()))
+    };
+
+    let server = &template.server;
+    MCPServer {
+        command: fill(&server.command),
+        args: server.args.iter().filter(|a| kept(a)).map(|a| fill(a)).collect(),
+        env: server.env.iter().filter(|(_, v)| kept(v)).map(|(k, v)| (k.clone(), fill(v))).collect(),
+        url: server.url.as_deref().map(fill),
+        headers: server.headers.iter().filter(|(_, v)| kept(v)).map(|(k, v)| (k.clone(), fill(v))).collect(),
+        always_allow: server.always_allow.clone(),
+    }
+    .expanded()
+}
+
+/// The server `template` makes with `values`, failing if any value is wrong.
+pub fn render(template: &ServerTemplate, values: &HashMap<String, String>) -> Result<MCPServer> {
+    ensure_valid(template, values)?;
+    Ok(fill_in(template, values))
+}
+
+/// `values` with secrets given in the clear swapped for references to
+/// `<server>.<parameter>` in the vault. They're only stored if `store` is set,
+/// so a preview leaves the vault alone.
+fn reference_secret_values(
+    template: &ServerTemplate,
+    server: &str,
+    values: &HashMap<String, String>,
+    store: bool,
+) -> Result<HashMap<String, String>> {
+    let mut referenced = values.clone();
+    for parameter in template.parameters.iter().filter(|p| p.kind == ParameterKind::Secret) {
+        let Some(value) = value_of(parameter, values).map(String::from) else {
+            continue;
+        };
+        if secret_name(&value).is_some() {
+            continue;
+        }
+        let secret: String = format!("{}.{}", server, parameter.name)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '-' })
+            .collect();
+        if store {
+            SecretVault::open()?.set(&secret, &value)?;
+        }
+        referenced.insert(parameter.name.clone(), secret_ref(&secret));
+    }
+    Ok(referenced)
+}
+
+/// Add a server made from `template` to the chosen clients. Secret values
+/// given in the clear are stored in the vault as `<server>.<parameter>` and
+/// referenced from the server's env. Returns the server and the values it was
+/// made with.
+pub fn add_from_template(
+    manager: &mut ConfigManager,
+    template: &ServerTemplate,
+    name: &str,
+    values: &HashMap<String, String>,
+    targets: &[ConfigType],
+) -> Result<(MCPServer, HashMap<String, String>)> {
+    if name.trim().is_empty() {
+        return Err(AppError::InvalidArgument("Server name can't be empty".to_string()));
+    }
+    if targets.is_empty() {
+        return Err(AppError::InvalidArgument("Choose at least one client".to_string()));
+    }
+    if manager.get_managed_servers()?.iter().any(|s| s.name == name) {
+        return Err(AppError::ServerExists(name.to_string()));
+    }
+
+    check(template)?;
+    ensure_valid(template, values)?;
+    let values = reference_secret_values(template, name, values, true)?;
+    let server = fill_in(template, &values);
+    manager.add_server(name, &server, targets)?;
+
+    // Describe the new server unless it already has metadata from before
+    let mut metadata = manager.get_state().metadata.get(name).cloned().unwrap_or_default();
+    metadata.description = metadata.description.or(Some(template.description.clone()));
+    metadata.source_url = metadata.source_url.or(template.source_url.clone());
+    manager.set_server_metadata(name, metadata)?;
+    Ok((server, values))
+}
+
+/// Add a server made from a catalog template, and remember which template
+/// and values it was made with.
+pub fn instantiate(manager: &mut ConfigManager, request: &InstantiateRequest) -> Result<MCPServer> {
+    let template = find(&manager.get_state().template_dirs, &request.template_id)?;
+    let name = request.name.clone().unwrap_or_else(|| template.id.clone());
+    let (server, values) = add_from_template(manager, &template, &name, &request.values, &request.targets)?;
+    manager.record_template_instance(
+        &name,
+        TemplateInstance {
+            template_id: template.id,
+            version: template.version,
+            values,
+        },
+    )?;
+    Ok(server)
+}
+
+/// Which template a server was made from, recorded in `AppState` by server name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateInstance {
+    pub template_id: String,
+    pub version: u32,
+    /// The parameter values it was made with, secrets as vault references
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+}
+
+/// A newer version of the template a server was made from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateUpgrade {
+    pub server: String,
+    pub template_id: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Problems with the server's values under the new version, such as a new
+    /// required parameter; the upgrade needs values for these
+    pub issues: Vec<ParameterIssue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeRequest {
+    /// Servers to upgrade; every server with an upgrade if empty
+    #[serde(default)]
+    pub servers: Vec<String>,
+    /// Extra values per server, for parameters the new version adds or changes
+    #[serde(default)]
+    pub values: HashMap<String, HashMap<String, String>>,
+}
+
+/// A server's recorded values that the new version still declares, plus `extra`.
+fn carried_values(
+    template: &ServerTemplate,
+    instance: &TemplateInstance,
+    extra: Option<&HashMap<String, String>>,
+) -> HashMap<String, String> {
+    let mut values: HashMap<String, String> = instance
+        .values
+        .iter()
+        .filter(|(key, _)| template.parameters.iter().any(|p| &p.name == *key))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    values.extend(extra.into_iter().flatten().map(|(k, v)| (k.clone(), v.clone())));
+    values
+}
+
+/// Servers whose template has a newer version than the one they were made from.
+pub fn upgrades(manager: &ConfigManager) -> Result<Vec<TemplateUpgrade>> {
+    let state = manager.get_state();
+    let catalog = catalog(&state.template_dirs)?;
+    let instances: BTreeMap<&String, &TemplateInstance> = state.template_instances.iter().collect();
+
+    let mut upgrades = Vec::new();
+    for (name, instance) in instances {
+        let Some(template) = catalog.templates.iter().find(|t| t.id == instance.template_id) else {
+            continue;
+        };
+        if template.version <= instance.version {
+            continue;
+        }
+        upgrades.push(TemplateUpgrade {
+            server: name.clone(),
+            template_id: template.id.clone(),
+            from_version: instance.version,
+            to_version: template.version,
+            issues: validate(template, &carried_values(template, instance, None)),
+        });
+    }
+    Ok(upgrades)
+}
+
+/// A server re-rendered from the newer version of its template.
+struct Upgraded {
+    name: String,
+    instance: TemplateInstance,
+    server: MCPServer,
+    reason: String,
+}
+
+fn prepare(manager: &ConfigManager, request: &UpgradeRequest, store: bool) -> Result<Vec<Upgraded>> {
+    let state = manager.get_state();
+    for name in &request.servers {
+        if !state.template_instances.contains_key(name) {
+            return Err(AppError::InvalidArgument(format!("{} wasn't made from a template", name)));
+        }
+    }
+
+    let catalog = catalog(&state.template_dirs)?;
+    let mut upgraded = Vec::new();
+    for upgrade in upgrades(manager)? {
+        if !request.servers.is_empty() && !request.servers.contains(&upgrade.server) {
+            continue;
+        }
+        let name = upgrade.server;
+        let Some(template) = catalog.templates.iter().find(|t| t.id == upgrade.template_id) else {
+            continue;
+        };
+        let values = carried_values(template, &state.template_instances[&name], request.values.get(&name));
+        let issues = validate(template, &values);
+        if !issues.is_empty() {
+            let issues: Vec<String> = issues.iter().map(|i| format!("{}: {}", i.parameter, i.message)).collect();
+            return Err(AppError::InvalidArgument(format!("{}: {}", name, issues.join("; "))));
+        }
+
+        let values = reference_secret_values(template, &name, &values, store)?;
+        upgraded.push(Upgraded {
+            server: fill_in(template, &values),
+            instance: TemplateInstance {
+                template_id: template.id.clone(),
+                version: template.version,
+                values,
+            },
+            reason: format!(
+                "Template {} updated from v{} to v{}",
+                template.id, upgrade.from_version, upgrade.to_version
+            ),
+            name,
+        });
+    }
+    Ok(upgraded)
+}
+
+/// The changes that re-render every enabled copy of the upgraded servers,
+/// keeping each client's pinned fields. Disabled copies are left as they are.
+/// Also returns the upgrades that found a copy to re-render; the rest have
+/// nothing to upgrade.
+fn plan_upgrades(manager: &ConfigManager, upgraded: Vec<Upgraded>) -> Result<(ChangePlan, Vec<Upgraded>)> {
+    let mut changes = Vec::new();
+    let mut found = BTreeSet::new();
+    for config_type in ConfigType::all() {
+        let config = recorder::unproxied_config(manager.read_config(config_type)?);
+        let current = ConfigManager::reference_secrets(&config.servers);
+        for upgrade in &upgraded {
+            let Some(copy) = current.get(&upgrade.name) else {
+                continue;
+            };
+            found.insert(upgrade.name.clone());
+            let pinned = manager.get_state().pinned(&upgrade.name, config_type);
+            let desired = keep_pinned(&upgrade.server, Some(copy), pinned);
+            changes.extend(PlannedChange::between(
+                &upgrade.name,
+                config_type,
+                Some(copy),
+                Some(&desired),
+                upgrade.reason.clone(),
+            ));
+        }
+    }
+    let plan = ChangePlan {
+        changes,
+        conflicts: Vec::new(),
+    };
+    Ok((plan, upgraded.into_iter().filter(|u| found.contains(&u.name)).collect()))
+}
+
+/// Plan the upgrades without writing anything. New secret values aren't stored yet.
+pub fn preview_upgrade(manager: &ConfigManager, request: &UpgradeRequest) -> Result<ChangePlan> {
+    Ok(plan_upgrades(manager, prepare(manager, request, false)?)?.0)
+}
+
+/// Re-render the servers from their newer templates with the values they were
+/// made with, make the results canonical, and record the new versions.
+/// Servers no client has enabled are left alone.
+pub fn apply_upgrade(manager: &mut ConfigManager, request: &UpgradeRequest) -> Result<ChangePlan> {
+    let (plan, upgraded) = plan_upgrades(manager, prepare(manager, request, true)?)?;
+    plan::apply(manager, &plan)?;
+
+    let mut registry = Registry::open()?;
+    for upgrade in upgraded {
+        registry.set(&HashMap::from([(upgrade.name.clone(), upgrade.server)]))?;
+        manager.record_template_instance(&upgrade.name, upgrade.instance)?;
+    }
+    Ok(plan)
+}
@@ -0,0 +1,64 @@
+use crate::config::ConfigType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+/// How often client config files are checked for changes
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Payload of the `config-changed` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChange {
+    pub config_type: ConfigType,
+    pub path: String,
+    pub exists: bool,
+}
+
+/// Notices when a client config is created, edited or deleted, whether by
+/// mcphub or by hand. Polls modification times, which is plenty for files
+/// that change a few times a day and needs no platform-specific APIs.
+pub struct ConfigWatcher {
+    seen: HashMap<ConfigType, Option<SystemTime>>,
+}
+
+fn modified(config_type: ConfigType) -> Option<SystemTime> {
+    let path = config_type.config_path().ok()?;
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl Default for ConfigWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigWatcher {
+    /// Start watching from the files' current state.
+    pub fn new() -> Self {
+        ConfigWatcher {
+            seen: ConfigType::all().into_iter().map(|ct| (ct, modified(ct))).collect(),
+        }
+    }
+
+    /// Configs that changed since the last poll.
+    pub fn poll(&mut self) -> Vec<ConfigChange> {
+        let mut changes = Vec::new();
+        for config_type in ConfigType::all() {
+            let current = modified(config_type);
+            if self.seen.insert(config_type, current) == Some(current) {
+                continue;
+            }
+            changes.push(ConfigChange {
+                config_type,
+                path: config_type
+                    .config_path()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                exists: current.is_some(),
+            });
+        }
+        changes
+    }
+}
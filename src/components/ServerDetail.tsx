@@ -126,6 +126,8 @@ export function ServerDetail() {
       command: server.command,
       args: server.args,
       env: server.env,
+      url: server.url,
+      headers: server.headers,
       alwaysAllow: server.alwaysAllow,
     });
     setTestResult(result);
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type {
   MCPConfig,
   ManagedServer,
@@ -9,22 +10,73 @@ import type {
   SyncRequest,
   ConfigType,
   AppState,
+  RunningServer,
+  ResourceSample,
+  ServerResourceSample,
+  ServerExit,
+  DaemonLost,
+  RecordingRequest,
+  RecordingSession,
+  RecordedMessage,
+  MessageFilter,
+  SecretInfo,
+  Redaction,
+  InterpolationIssue,
+  ImportRequest,
+  ImportPreview,
+  ImportReport,
+  ExportBundleRequest,
+  ImportBundleRequest,
+  BundlePreview,
+  BundleImportReport,
+  BundlePolicy,
+  SigningIdentity,
+  TrustedKey,
+  Verification,
+  ConfigChange,
+  HealthCheck,
+  ConfigDiff,
+  DriftReport,
+  SyncStrategy,
+  SyncRule,
+  Profile,
+  Catalog,
+  ParameterIssue,
+  InstantiateRequest,
+  TemplateUpgrade,
+  UpgradeRequest,
+  ServerTemplate,
+  MarketplaceIndex,
+  MarketplaceQuery,
+  MarketplaceServer,
+  InstallRequest,
+  ServerMetadata,
+  ServerQuery,
+  ChangePlan,
 } from '../types/mcp';
 
 export async function getAllConfigs(): Promise<MCPConfig[]> {
   return invoke<MCPConfig[]>('get_all_configs');
 }
 
-export async function getManagedServers(): Promise<ManagedServer[]> {
-  return invoke<ManagedServer[]>('get_managed_servers');
+export async function getManagedServers(query?: ServerQuery): Promise<ManagedServer[]> {
+  return invoke<ManagedServer[]>('get_managed_servers', { query });
 }
 
 export async function saveServer(request: SaveServerRequest): Promise<void> {
   return invoke('save_server', { request });
 }
 
-export async function deleteServer(name: string): Promise<void> {
-  return invoke('delete_server', { name });
+export async function deleteServer(name: string, targets?: ConfigType[]): Promise<void> {
+  return invoke('delete_server', { name, targets });
+}
+
+export async function renameServer(name: string, newName: string): Promise<void> {
+  return invoke('rename_server', { name, newName });
+}
+
+export async function setServerMetadata(name: string, metadata: ServerMetadata): Promise<void> {
+  return invoke('set_server_metadata', { name, metadata });
 }
 
 export async function setServerEnabled(request: SetEnabledRequest): Promise<void> {
@@ -35,12 +87,96 @@ export async function testServerConnection(name: string, server: MCPServer): Pro
   return invoke<TestResult>('test_server_connection', { name, server });
 }
 
+export async function checkVariables(request: SaveServerRequest): Promise<InterpolationIssue[]> {
+  return invoke<InterpolationIssue[]>('check_variables', { request });
+}
+
 export async function syncServer(request: SyncRequest): Promise<void> {
   return invoke('sync_server', { request });
 }
 
-export async function syncAllServers(source: ConfigType): Promise<void> {
-  return invoke('sync_all_servers', { source });
+export async function syncAllServers(
+  source: ConfigType,
+  strategy?: SyncStrategy,
+  resolutions?: Record<string, ConfigType>
+): Promise<ChangePlan> {
+  return invoke<ChangePlan>('sync_all_servers', { source, strategy, resolutions });
+}
+
+export async function previewSync(
+  source: ConfigType,
+  strategy?: SyncStrategy,
+  resolutions?: Record<string, ConfigType>
+): Promise<ChangePlan> {
+  return invoke<ChangePlan>('preview_sync', { source, strategy, resolutions });
+}
+
+export async function setSyncRule(name: string, rule: SyncRule): Promise<void> {
+  return invoke('set_sync_rule', { name, rule });
+}
+
+export async function saveProfile(name: string, profile?: Profile): Promise<void> {
+  return invoke('save_profile', { name, profile });
+}
+
+export async function deleteProfile(name: string): Promise<void> {
+  return invoke('delete_profile', { name });
+}
+
+export async function previewProfile(name: string): Promise<ChangePlan> {
+  return invoke<ChangePlan>('preview_profile', { name });
+}
+
+export async function applyProfile(name: string): Promise<ChangePlan> {
+  return invoke<ChangePlan>('apply_profile', { name });
+}
+
+export async function getActiveProfile(): Promise<string | null> {
+  return invoke<string | null>('get_active_profile');
+}
+
+export async function listTemplates(): Promise<Catalog> {
+  return invoke<Catalog>('list_templates');
+}
+
+export async function setTemplateDirs(dirs: string[]): Promise<void> {
+  return invoke('set_template_dirs', { dirs });
+}
+
+export async function validateTemplate(id: string, values: Record<string, string>): Promise<ParameterIssue[]> {
+  return invoke<ParameterIssue[]>('validate_template', { id, values });
+}
+
+export async function instantiateTemplate(request: InstantiateRequest): Promise<MCPServer> {
+  return invoke<MCPServer>('instantiate_template', { request });
+}
+
+export async function getTemplateUpgrades(): Promise<TemplateUpgrade[]> {
+  return invoke<TemplateUpgrade[]>('get_template_upgrades');
+}
+
+export async function previewTemplateUpgrade(request: UpgradeRequest): Promise<ChangePlan> {
+  return invoke<ChangePlan>('preview_template_upgrade', { request });
+}
+
+export async function applyTemplateUpgrade(request: UpgradeRequest): Promise<ChangePlan> {
+  return invoke<ChangePlan>('apply_template_upgrade', { request });
+}
+
+export async function refreshMarketplace(source?: string): Promise<MarketplaceIndex> {
+  return invoke<MarketplaceIndex>('refresh_marketplace', { source });
+}
+
+export async function searchMarketplace(query?: MarketplaceQuery): Promise<MarketplaceServer[]> {
+  return invoke<MarketplaceServer[]>('search_marketplace', { query });
+}
+
+export async function getMarketplaceTemplate(server: string, pkg?: string): Promise<ServerTemplate> {
+  return invoke<ServerTemplate>('get_marketplace_template', { server, package: pkg });
+}
+
+export async function installFromMarketplace(request: InstallRequest): Promise<MCPServer> {
+  return invoke<MCPServer>('install_from_marketplace', { request });
 }
 
 export async function backupConfigs(): Promise<string[]> {
@@ -51,10 +187,150 @@ export async function getAppState(): Promise<AppState> {
   return invoke<AppState>('get_app_state');
 }
 
-export async function importConfig(json: string): Promise<void> {
-  return invoke('import_config', { json });
+export async function diffConfigs(a: ConfigType, b: ConfigType): Promise<ConfigDiff> {
+  return invoke<ConfigDiff>('diff_configs', { a, b });
+}
+
+export async function getDriftReport(): Promise<DriftReport> {
+  return invoke<DriftReport>('get_drift_report');
+}
+
+export async function findImportPlaceholders(json: string): Promise<string[]> {
+  return invoke<string[]>('find_import_placeholders', { json });
+}
+
+export async function previewImport(json: string, targets: ConfigType[]): Promise<ImportPreview> {
+  return invoke<ImportPreview>('preview_import', { json, targets });
+}
+
+export async function importConfig(request: ImportRequest): Promise<ImportReport> {
+  return invoke<ImportReport>('import_config', { request });
+}
+
+export async function exportConfig(source: ConfigType, redact?: boolean): Promise<string> {
+  return invoke<string>('export_config', { source, redact });
+}
+
+export async function previewRedactions(source: ConfigType): Promise<Redaction[]> {
+  return invoke<Redaction[]>('preview_redactions', { source });
+}
+
+export async function exportBundle(request: ExportBundleRequest): Promise<string> {
+  return invoke<string>('export_bundle', { request });
+}
+
+export async function previewBundle(json: string, targets?: ConfigType[]): Promise<BundlePreview> {
+  return invoke<BundlePreview>('preview_bundle', { json, targets });
+}
+
+export async function importBundle(request: ImportBundleRequest): Promise<BundleImportReport> {
+  return invoke<BundleImportReport>('import_bundle', { request });
+}
+
+export async function getSigningIdentity(): Promise<SigningIdentity | null> {
+  return invoke<SigningIdentity | null>('get_signing_identity');
+}
+
+export async function generateSigningKey(name: string, overwrite?: boolean): Promise<SigningIdentity> {
+  return invoke<SigningIdentity>('generate_signing_key', { name, overwrite });
+}
+
+export async function signBundle(json: string): Promise<string> {
+  return invoke<string>('sign_bundle', { json });
+}
+
+export async function verifyBundle(json: string): Promise<Verification> {
+  return invoke<Verification>('verify_bundle', { json });
+}
+
+export async function listTrustedKeys(): Promise<TrustedKey[]> {
+  return invoke<TrustedKey[]>('list_trusted_keys');
+}
+
+export async function trustKey(name: string, publicKey: string): Promise<void> {
+  return invoke('trust_key', { name, publicKey });
+}
+
+export async function removeTrustedKey(publicKey: string): Promise<void> {
+  return invoke('remove_trusted_key', { publicKey });
+}
+
+export async function setBundlePolicy(policy: BundlePolicy): Promise<void> {
+  return invoke('set_bundle_policy', { policy });
+}
+
+export async function startServer(name: string, server: MCPServer): Promise<RunningServer> {
+  return invoke<RunningServer>('start_server', { name, server });
+}
+
+export async function stopServer(name: string): Promise<void> {
+  return invoke('stop_server', { name });
+}
+
+export async function getRunningServers(): Promise<RunningServer[]> {
+  return invoke<RunningServer[]>('get_running_servers');
+}
+
+export async function getResourceSamples(name: string): Promise<ResourceSample[]> {
+  return invoke<ResourceSample[]>('get_resource_samples', { name });
+}
+
+export async function onServerResources(handler: (sample: ServerResourceSample) => void): Promise<UnlistenFn> {
+  return listen<ServerResourceSample>('server-resources', (event) => handler(event.payload));
+}
+
+export async function onServerExited(handler: (exit: ServerExit) => void): Promise<UnlistenFn> {
+  return listen<ServerExit>('server-exited', (event) => handler(event.payload));
+}
+
+export async function onConfigChanged(handler: (change: ConfigChange) => void): Promise<UnlistenFn> {
+  return listen<ConfigChange>('config-changed', (event) => handler(event.payload));
+}
+
+export async function onTemplatesChanged(handler: (catalog: Catalog) => void): Promise<UnlistenFn> {
+  return listen<Catalog>('templates-changed', (event) => handler(event.payload));
+}
+
+export async function onDaemonLost(handler: (lost: DaemonLost) => void): Promise<UnlistenFn> {
+  return listen<DaemonLost>('daemon-lost', (event) => handler(event.payload));
+}
+
+export async function onServerHealth(handler: (check: HealthCheck) => void): Promise<UnlistenFn> {
+  return listen<HealthCheck>('server-health', (event) => handler(event.payload));
+}
+
+export async function getGatewayEntry(): Promise<MCPServer> {
+  return invoke<MCPServer>('get_gateway_entry');
+}
+
+export async function installGateway(targets: ConfigType[]): Promise<void> {
+  return invoke('install_gateway', { targets });
+}
+
+export async function enableRecording(request: RecordingRequest): Promise<void> {
+  return invoke('enable_recording', { request });
+}
+
+export async function disableRecording(request: RecordingRequest): Promise<void> {
+  return invoke('disable_recording', { request });
+}
+
+export async function listRecordings(server?: string): Promise<RecordingSession[]> {
+  return invoke<RecordingSession[]>('list_recordings', { server });
+}
+
+export async function getRecording(id: string, filter?: MessageFilter): Promise<RecordedMessage[]> {
+  return invoke<RecordedMessage[]>('get_recording', { id, filter });
+}
+
+export async function listSecrets(): Promise<SecretInfo[]> {
+  return invoke<SecretInfo[]>('list_secrets');
+}
+
+export async function setSecret(name: string, value: string): Promise<void> {
+  return invoke('set_secret', { name, value });
 }
 
-export async function exportConfig(source: ConfigType): Promise<string> {
-  return invoke<string>('export_config', { source });
+export async function deleteSecret(name: string): Promise<void> {
+  return invoke('delete_secret', { name });
 }
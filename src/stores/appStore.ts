@@ -1,5 +1,5 @@
 import { create } from 'zustand';
-import type { ManagedServer, MCPConfig, ConfigType, MCPServer } from '../types/mcp';
+import type { ManagedServer, MCPConfig, ConfigType, MCPServer, ServerMetadata, SyncStrategy } from '../types/mcp';
 import * as api from '../lib/tauri';
 
 export type ViewMode = 'list' | 'grid';
@@ -33,12 +33,18 @@ interface AppStore {
   setSyncModalOpen: (open: boolean) => void;
 
   // Server operations
-  saveServer: (name: string, server: MCPServer, targets: ConfigType[], originalName?: string) => Promise<void>;
+  saveServer: (
+    name: string,
+    server: MCPServer,
+    targets: ConfigType[],
+    originalName?: string,
+    metadata?: ServerMetadata
+  ) => Promise<void>;
   deleteServer: (name: string) => Promise<void>;
   setServerEnabled: (name: string, configType: ConfigType, enabled: boolean) => Promise<void>;
   testServer: (name: string, server: MCPServer) => Promise<{ success: boolean; message: string }>;
   syncServer: (name: string, source: ConfigType, targets: ConfigType[]) => Promise<void>;
-  syncAll: (source: ConfigType) => Promise<void>;
+  syncAll: (source: ConfigType, strategy?: SyncStrategy) => Promise<void>;
   backupAll: () => Promise<string[]>;
 
   // Computed
@@ -90,9 +96,9 @@ export const useAppStore = create<AppStore>((set, get) => ({
 
   setSyncModalOpen: (open) => set({ isSyncModalOpen: open }),
 
-  saveServer: async (name, server, targets, originalName) => {
+  saveServer: async (name, server, targets, originalName, metadata) => {
     try {
-      await api.saveServer({ name, server, targets, originalName });
+      await api.saveServer({ name, server, targets, originalName, metadata });
       await get().loadData();
       set({ selectedServer: name });
     } catch (err) {
@@ -143,9 +149,9 @@ export const useAppStore = create<AppStore>((set, get) => ({
     }
   },
 
-  syncAll: async (source) => {
+  syncAll: async (source, strategy) => {
     try {
-      await api.syncAllServers(source);
+      await api.syncAllServers(source, strategy);
       await get().loadData();
     } catch (err) {
       set({ error: String(err) });
@@ -6,12 +6,18 @@ export interface MCPServer {
   command: string;
   args: string[];
   env: Record<string, string>;
+  url?: string;
+  headers?: Record<string, string>;
   alwaysAllow: string[];
 }
 
+export type DriftStatus = 'inSync' | 'drifted';
+
 export interface SystemStatus {
   enabled: boolean;
   present: boolean;
+  drift: DriftStatus;
+  driftedFields: string[];
 }
 
 export interface ManagedServer {
@@ -19,11 +25,30 @@ export interface ManagedServer {
   command: string;
   args: string[];
   env: Record<string, string>;
+  url?: string;
+  headers: Record<string, string>;
   alwaysAllow: string[];
   systems: Record<string, SystemStatus>;
   health: HealthStatus;
   lastTested?: string;
   errorMessage?: string;
+  metadata: ServerMetadata;
+}
+
+export interface ServerMetadata {
+  tags: string[];
+  group?: string;
+  description?: string;
+  owner?: string;
+  sourceUrl?: string;
+  notes?: string;
+}
+
+export interface ServerQuery {
+  tags?: string[];
+  group?: string;
+  owner?: string;
+  text?: string;
 }
 
 export interface MCPConfig {
@@ -32,6 +57,7 @@ export interface MCPConfig {
   exists: boolean;
   isValid: boolean;
   servers: Record<string, MCPServer>;
+  disabled: Record<string, MCPServer>;
   lastModified?: string;
   error?: string;
 }
@@ -40,6 +66,21 @@ export interface AppState {
   servers: Record<string, ServerState>;
   lastSync?: string;
   version: string;
+  bundlePolicy: BundlePolicy;
+  syncRules: Record<string, SyncRule>;
+  profiles: Record<string, Profile>;
+  metadata: Record<string, ServerMetadata>;
+  templateDirs: string[];
+  templateInstances: Record<string, TemplateInstance>;
+  gatewayServers: string[];
+}
+
+export interface Profile {
+  enabled: Record<string, ConfigType[]>;
+}
+
+export interface SyncRule {
+  pinned: Partial<Record<ConfigType, string[]>>;
 }
 
 export interface ServerState {
@@ -48,6 +89,216 @@ export interface ServerState {
   errorMessage?: string;
 }
 
+export type DiffStatus = 'onlyInA' | 'onlyInB' | 'identical' | 'differing';
+
+export type ChangeKind = 'added' | 'removed' | 'modified';
+
+export interface FieldChange {
+  field: string;
+  kind: ChangeKind;
+  a: unknown | null;
+  b: unknown | null;
+}
+
+export interface ServerDiff {
+  name: string;
+  status: DiffStatus;
+  changes: FieldChange[];
+}
+
+export interface ConfigDiff {
+  a: ConfigType;
+  b: ConfigType;
+  servers: ServerDiff[];
+}
+
+export type SyncStrategy = 'sourceOfTruth' | 'union' | 'intersection' | 'preferNewest' | 'threeWay';
+
+export type PlanAction = 'add' | 'update' | 'remove' | 'enable' | 'disable';
+
+export interface PlannedChange {
+  server: string;
+  configType: ConfigType;
+  action: PlanAction;
+  before: MCPServer | null;
+  after: MCPServer | null;
+  fields: FieldChange[];
+  reason: string;
+}
+
+export interface ConflictVersion {
+  configType: ConfigType;
+  server: MCPServer | null;
+  fields: FieldChange[];
+}
+
+export interface Conflict {
+  server: string;
+  base: MCPServer | null;
+  versions: ConflictVersion[];
+}
+
+export interface ChangePlan {
+  changes: PlannedChange[];
+  conflicts: Conflict[];
+}
+
+export interface ClientDrift {
+  configType: ConfigType;
+  enabled: boolean;
+  status: DriftStatus;
+  fields: FieldChange[];
+  changedAt: string | null;
+}
+
+export interface SuggestedSync {
+  source: ConfigType;
+  targets: ConfigType[];
+}
+
+export interface DriftEntry {
+  server: string;
+  clients: ClientDrift[];
+  suggestion: SuggestedSync | null;
+  reason: string;
+}
+
+export interface DriftReport {
+  servers: DriftEntry[];
+}
+
+export type ParameterKind = 'text' | 'path' | 'secret' | 'url' | 'enum';
+
+export interface TemplateParameter {
+  name: string;
+  label: string;
+  kind: ParameterKind;
+  description?: string;
+  required: boolean;
+  default?: string;
+  options: string[];
+  schemes: string[];
+}
+
+export interface ServerTemplate {
+  id: string;
+  version: number;
+  name: string;
+  description: string;
+  sourceUrl?: string;
+  parameters: TemplateParameter[];
+  server: MCPServer;
+  source?: string;
+  overridesBuiltin: boolean;
+}
+
+export interface TemplateProblem {
+  path: string;
+  message: string;
+}
+
+export interface Catalog {
+  templates: ServerTemplate[];
+  problems: TemplateProblem[];
+}
+
+export interface ParameterIssue {
+  parameter: string;
+  message: string;
+}
+
+export interface InstantiateRequest {
+  templateId: string;
+  name?: string;
+  values: Record<string, string>;
+  targets: ConfigType[];
+}
+
+export interface TemplateInstance {
+  templateId: string;
+  version: number;
+  values: Record<string, string>;
+}
+
+export interface TemplateUpgrade {
+  server: string;
+  templateId: string;
+  fromVersion: number;
+  toVersion: number;
+  issues: ParameterIssue[];
+}
+
+export interface UpgradeRequest {
+  servers: string[];
+  values: Record<string, Record<string, string>>;
+}
+
+export interface MarketplaceInput {
+  name: string;
+  description?: string;
+  value?: string;
+  default?: string;
+  isRequired: boolean;
+  isSecret: boolean;
+  format?: string;
+  choices: string[];
+  variables: Record<string, MarketplaceInput>;
+}
+
+export interface MarketplaceArgument extends MarketplaceInput {
+  type: 'positional' | 'named';
+  valueHint?: string;
+}
+
+export interface MarketplaceTransport {
+  type: string;
+  url?: string;
+  headers: MarketplaceInput[];
+}
+
+export interface MarketplacePackage {
+  registryType: string;
+  identifier: string;
+  version?: string;
+  runtimeHint?: string;
+  transport: MarketplaceTransport;
+  runtimeArguments: MarketplaceArgument[];
+  packageArguments: MarketplaceArgument[];
+  environmentVariables: MarketplaceInput[];
+}
+
+export interface MarketplaceServer {
+  name: string;
+  title?: string;
+  description: string;
+  version?: string;
+  repository?: { url?: string; source?: string };
+  websiteUrl?: string;
+  packages: MarketplacePackage[];
+  remotes: MarketplaceTransport[];
+  tags: string[];
+}
+
+export interface MarketplaceIndex {
+  source?: string;
+  fetchedAt?: string;
+  servers: MarketplaceServer[];
+}
+
+export interface MarketplaceQuery {
+  name?: string;
+  tag?: string;
+  package?: string;
+}
+
+export interface InstallRequest {
+  server: string;
+  name?: string;
+  package?: string;
+  values: Record<string, string>;
+  targets: ConfigType[];
+}
+
 export interface TestResult {
   success: boolean;
   message: string;
@@ -59,6 +310,7 @@ export interface SaveServerRequest {
   server: MCPServer;
   targets: ConfigType[];
   originalName?: string;
+  metadata?: ServerMetadata;
 }
 
 export interface SetEnabledRequest {
@@ -73,6 +325,232 @@ export interface SyncRequest {
   targets: ConfigType[];
 }
 
+export interface ResourceSample {
+  timestamp: string;
+  pid: number;
+  cpuPercent: number;
+  rssBytes: number;
+  openFds?: number;
+  childProcesses: number;
+}
+
+export interface RunningServer {
+  name: string;
+  pid: number;
+  startedAt: string;
+  latest?: ResourceSample;
+}
+
+export interface ServerResourceSample {
+  name: string;
+  sample: ResourceSample;
+}
+
+export interface ServerExit {
+  name: string;
+  pid: number;
+  code?: number;
+}
+
+export interface DaemonLost {
+  orphaned: RunningServer[];
+}
+
+export interface ConfigChange {
+  configType: ConfigType;
+  path: string;
+  exists: boolean;
+}
+
+export interface HealthCheck {
+  name: string;
+  health: HealthStatus;
+  error?: string;
+}
+
+export interface RecordingRequest {
+  name: string;
+  configType: ConfigType;
+}
+
+export type MessageDirection = 'clientToServer' | 'serverToClient';
+
+export type MessageKind = 'request' | 'response' | 'notification' | 'invalid';
+
+export interface RecordingSession {
+  id: string;
+  server: string;
+  startedAt: string;
+  lastMessageAt?: string;
+  messageCount: number;
+  path: string;
+}
+
+export interface RecordedMessage {
+  timestamp: string;
+  direction: MessageDirection;
+  kind: MessageKind;
+  method?: string;
+  id?: string | number;
+  message: unknown;
+}
+
+export interface MessageFilter {
+  direction?: MessageDirection;
+  kind?: MessageKind;
+  method?: string;
+  text?: string;
+}
+
+export interface SecretInfo {
+  name: string;
+  updatedAt: string;
+  usedBy: string[];
+}
+
+export const SECRET_SCHEME = 'mcphub-secret://';
+
+export type Detection = 'keyName' | 'tokenPrefix' | 'entropy' | 'vaultReference';
+
+export interface Redaction {
+  placeholder: string;
+  server: string;
+  location: string;
+  detection: Detection;
+}
+
+export const PLACEHOLDER_SCHEME = 'mcphub-placeholder://';
+
+export type ImportStrategy = 'skip' | 'overwrite' | 'rename' | 'keepNewest';
+
+export interface ImportOptions {
+  strategies?: Record<string, ImportStrategy>;
+  defaultStrategy?: ImportStrategy;
+  values?: Record<string, string>;
+  sourceModified?: string;
+}
+
+export interface ImportRequest extends ImportOptions {
+  json: string;
+  targets: ConfigType[];
+}
+
+export interface ImportCollision {
+  name: string;
+  configType: ConfigType;
+  existing: MCPServer;
+  incoming: MCPServer;
+  identical: boolean;
+}
+
+export interface ImportPreview {
+  servers: string[];
+  placeholders: string[];
+  collisions: ImportCollision[];
+}
+
+export type ImportAction = 'added' | 'overwritten' | 'renamed' | 'skipped' | 'unchanged';
+
+export interface ImportChange {
+  name: string;
+  writtenAs: string | null;
+  configType: ConfigType;
+  action: ImportAction;
+}
+
+export interface ImportReport {
+  changes: ImportChange[];
+  backups: string[];
+}
+
+export const BUNDLE_SCHEMA_VERSION = 2;
+
+export interface BundleServer {
+  server: MCPServer;
+  clients: ConfigType[];
+  template?: TemplateInstance;
+}
+
+export interface BundlePlaceholder {
+  name: string;
+  usedBy: string[];
+}
+
+export interface Bundle {
+  schemaVersion: number;
+  author?: string;
+  description?: string;
+  createdAt: string | null;
+  servers: Record<string, BundleServer>;
+  templates: ServerTemplate[];
+  placeholders: BundlePlaceholder[];
+  signature?: BundleSignature;
+}
+
+export interface ExportBundleRequest {
+  servers?: string[];
+  author?: string;
+  description?: string;
+  redact?: boolean;
+  sign?: boolean;
+}
+
+export interface ImportBundleRequest extends ImportOptions {
+  json: string;
+  targets?: ConfigType[];
+  trustSigner?: boolean;
+}
+
+export interface BundlePreview {
+  bundle: Bundle;
+  verification: Verification;
+  import: ImportPreview;
+}
+
+export interface BundleImportReport extends ImportReport {
+  verification: Verification;
+  leftOutTemplates: string[];
+}
+
+export type BundlePolicy = 'warn' | 'block';
+
+export interface BundleSignature {
+  signer: string;
+  publicKey: string;
+  signature: string;
+}
+
+export type SignatureStatus = 'unsigned' | 'invalid' | 'trusted' | 'untrusted' | 'keyChanged';
+
+export interface Verification {
+  status: SignatureStatus;
+  signer: string | null;
+  fingerprint: string | null;
+  message: string;
+}
+
+export interface SigningIdentity {
+  name: string;
+  publicKey: string;
+  fingerprint: string;
+  createdAt: string;
+}
+
+export interface TrustedKey {
+  name: string;
+  publicKey: string;
+  fingerprint: string;
+  addedAt: string;
+}
+
+export interface InterpolationIssue {
+  server: string;
+  configType: ConfigType;
+  field: string;
+  variable: string;
+  message: string;
+}
+
 export const CONFIG_TYPE_INFO: Record<ConfigType, { displayName: string; shortName: string; color: string }> = {
   claudeCode: { displayName: 'Claude Code', shortName: 'CC', color: 'brand' },
   claudeDesktop: { displayName: 'Claude Desktop', shortName: 'CD', color: 'purple' },